@@ -1,5 +1,12 @@
 /// Allows for the state to be saved into and loaded from a file
 pub mod save;
+/// Seeded random numbers
+pub mod rng;
+/// Saves what is drawn as PNG files
+pub mod screenshot;
+
+use ggez::event::Keycode;
+use Controls;
 
 /// Extensions for booleans
 pub trait BoolExt {
@@ -14,11 +21,13 @@ impl BoolExt for bool {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
 /// Tracks how many buttons are being pressed in specific directions
 pub struct InputState {
-    /// Buttons in the left-right direction
-    pub hor: i8,
+    /// Buttons for turning anticlockwise and clockwise
+    pub rot: i8,
+    /// Buttons for strafing left and right
+    pub strafe: i8,
     /// Buttons in the up-down direction
     pub ver: i8,
     /// Buttons for turning throttle up and down
@@ -27,9 +36,14 @@ pub struct InputState {
 
 impl InputState {
     #[inline]
-    /// Returns `-1`, `0` or `1` depending on whether `self.hor` is negative, zero or positive
-    pub fn hor(&self) -> f32 {
-        self.hor.signum() as f32
+    /// Returns `-1`, `0` or `1` depending on whether `self.rot` is negative, zero or positive
+    pub fn rot(&self) -> f32 {
+        self.rot.signum() as f32
+    }
+    #[inline]
+    /// Returns `-1`, `0` or `1` depending on whether `self.strafe` is negative, zero or positive
+    pub fn strafe(&self) -> f32 {
+        self.strafe.signum() as f32
     }
     /// Returns `-1`, `0` or `1` depending on whether `self.ver` is negative, zero or positive
     #[inline]
@@ -41,4 +55,30 @@ impl InputState {
     pub fn throttle(&self) -> f32 {
         self.throttle.signum() as f32
     }
+    /// Whether any of the buttons for flying the ship are held
+    pub fn is_steering(&self) -> bool {
+        self.ver != 0 || self.rot != 0 || self.strafe != 0
+    }
+    /// The control commands for a ship from the buttons being held
+    pub fn controls(&self) -> Controls {
+        Controls {
+            main: self.ver() == 1.,
+            reverse: if self.ver() == -1. { 1. } else { 0. },
+            strafe: self.strafe(),
+            turn: self.rot(),
+        }
+    }
+}
+
+/// Gets the character a key types when entering a name, if any
+pub fn keycode_char(keycode: Keycode) -> Option<char> {
+    // Letter and number keycodes are the same as their lowercase ASCII characters
+    let c = keycode as i32;
+    if (c >= 'a' as i32 && c <= 'z' as i32) || (c >= '0' as i32 && c <= '9' as i32) {
+        Some((c as u8 as char).to_ascii_uppercase())
+    } else if keycode == Keycode::Space {
+        Some(' ')
+    } else {
+        None
+    }
 }
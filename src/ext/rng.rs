@@ -0,0 +1,49 @@
+/// A small seeded random number generator (SplitMix64)
+///
+/// Used instead of an outside crate so the same seed always gives the same numbers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Makes a new generator from a seed
+    pub fn new(seed: u64) -> Self {
+        Rng {
+            state: seed,
+        }
+    }
+    /// Makes a generator from a seed and a pair of coordinates
+    ///
+    /// Every coordinate gets its own independent stream of numbers
+    pub fn with_coords(seed: u64, x: i32, y: i32) -> Self {
+        let mut rng = Rng::new(seed ^ (x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+        let a = rng.next_u64();
+        Rng::new(a ^ (y as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F))
+    }
+    /// Gets the next random number
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+    /// A random number from `0` up to but not including `1`
+    pub fn next_f32(&mut self) -> f32 {
+        // Use the highest 24 bits since that's how many fit in an `f32`
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+    /// A random number from `low` up to but not including `high`
+    pub fn range(&mut self, low: f32, high: f32) -> f32 {
+        low + (high - low) * self.next_f32()
+    }
+    /// A random integer from `low` up to but not including `high`
+    pub fn range_u32(&mut self, low: u32, high: u32) -> u32 {
+        low + (self.next_u64() % (high - low) as u64) as u32
+    }
+    /// Returns `true` with the given probability
+    pub fn chance(&mut self, p: f32) -> bool {
+        self.next_f32() < p
+    }
+}
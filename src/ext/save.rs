@@ -4,6 +4,7 @@ use ggez::error::GameError;
 
 use serde::{Serialize, Deserialize, Serializer, Deserializer};
 use game::world::World;
+use game::score::HighScores;
 use ::{bincode, Vector2, Point2, GameResult};
 
 /// Save the state in a file
@@ -21,6 +22,19 @@ pub fn load<P: AsRef<Path>>(path: P, w: &mut World) -> GameResult<()> {
     *w = world;
     Ok(())
 }
+/// Save the high-score table in a file
+pub fn save_scores<P: AsRef<Path>>(path: P, scores: &HighScores) -> GameResult<()> {
+    let mut file = File::create(path)?;
+    bincode::serialize_into(&mut file, scores, bincode::Infinite)
+        .map_err(|e| GameError::UnknownError(format!("{:?}", e)))?;
+    Ok(())
+}
+/// Load the high-score table from a file
+pub fn load_scores<P: AsRef<Path>>(path: P) -> GameResult<HighScores> {
+    let mut file = File::open(path)?;
+    bincode::deserialize_from(&mut file, bincode::Infinite)
+        .map_err(|e| GameError::UnknownError(format!("{:?}", e)))
+}
 /// Serialize a `Point2`
 pub fn point_ser<S: Serializer>(p: &Point2, ser: S) -> Result<S::Ok, S::Error> {
     (p.x, p.y).serialize(ser)
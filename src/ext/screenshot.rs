@@ -0,0 +1,108 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ggez::{Context, GameResult, GameError};
+use ggez::graphics::{self, Canvas};
+use gfx::memory::Typed;
+use gfx_device_gl::NewTexture;
+use gfx_gl as gl;
+use image;
+
+/// Directory the screenshots are saved in
+pub const SCREENSHOT_DIR: &str = "screenshots";
+
+/// Reads what has been drawn to the screen this frame as RGBA rows from the top
+///
+/// Has to be called before `graphics::present`, the size is the drawable size of the window
+pub fn read_screen(ctx: &mut Context) -> (Vec<u8>, u32, u32) {
+    let (width, height) = graphics::get_drawable_size(ctx);
+    let mut pixels = vec![0u8; width as usize * height as usize * 4];
+    {
+        // Make sure everything queued has actually been drawn
+        let (_, device, encoder, _, _) = graphics::get_gfx_objects(ctx);
+        encoder.flush(device);
+        unsafe {
+            device.with_gl(|context| {
+                context.BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+                context.ReadBuffer(gl::BACK);
+                context.PixelStorei(gl::PACK_ALIGNMENT, 1);
+                context.ReadPixels(0, 0, width as i32, height as i32, gl::RGBA, gl::UNSIGNED_BYTE,
+                    pixels.as_mut_ptr() as *mut _);
+            });
+        }
+    }
+
+    (flip_opaque(&pixels, width, height), width, height)
+}
+
+/// Reads what has been drawn to a canvas as RGBA rows from the top
+///
+/// Unlike the screen, this works when the window is covered or minimised.
+/// Drawing goes to the screen afterwards
+pub fn read_canvas(ctx: &mut Context, canvas: &Canvas) -> GameResult<Vec<u8>> {
+    let (width, height) = (canvas.get_image().width(), canvas.get_image().height());
+    let mut pixels = vec![0u8; width as usize * height as usize * 4];
+    // Setting the canvas makes it the target given by `get_gfx_objects`
+    graphics::set_canvas(ctx, Some(canvas));
+    let result = {
+        let (_, device, encoder, _, target) = graphics::get_gfx_objects(ctx);
+        encoder.flush(device);
+        match *target.raw().get_texture().resource() {
+            NewTexture::Texture(texture) => {
+                unsafe {
+                    device.with_gl(|context| {
+                        context.BindTexture(gl::TEXTURE_2D, texture);
+                        context.PixelStorei(gl::PACK_ALIGNMENT, 1);
+                        context.GetTexImage(gl::TEXTURE_2D, 0, gl::RGBA, gl::UNSIGNED_BYTE,
+                            pixels.as_mut_ptr() as *mut _);
+                        context.BindTexture(gl::TEXTURE_2D, 0);
+                    });
+                }
+                Ok(())
+            }
+            NewTexture::Surface(_) => Err(GameError::RenderError("The canvas isn't a texture".to_owned())),
+        }
+    };
+    graphics::set_canvas(ctx, None);
+    result.map(|()| flip_opaque(&pixels, width, height))
+}
+
+/// Turns rows of pixels from OpenGL upside down, since it starts from the bottom row, and makes them opaque
+fn flip_opaque(pixels: &[u8], width: u32, height: u32) -> Vec<u8> {
+    let row = width as usize * 4;
+    let mut flipped = Vec::with_capacity(pixels.len());
+    for y in (0..height as usize).rev() {
+        flipped.extend_from_slice(&pixels[y * row..(y + 1) * row]);
+    }
+    // The window isn't see-through, so don't let the image be
+    for a in flipped.iter_mut().skip(3).step_by(4) {
+        *a = 255;
+    }
+    flipped
+}
+
+/// A new path in the screenshot directory named after the current time
+pub fn new_path(kind: &str) -> GameResult<PathBuf> {
+    fs::create_dir_all(SCREENSHOT_DIR)?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    let mut path = PathBuf::from(SCREENSHOT_DIR);
+    path.push(format!("{}-{}{:03}.png", kind, now.as_secs(), now.subsec_nanos() / 1_000_000));
+    Ok(path)
+}
+
+/// Saves RGBA pixels as a PNG file in the screenshot directory and returns its path
+pub fn save_png(kind: &str, pixels: &[u8], width: u32, height: u32) -> GameResult<PathBuf> {
+    let path = new_path(kind)?;
+    image::save_buffer(&path, pixels, width, height, image::RGBA(8))?;
+    Ok(path)
+}
+
+/// Saves what has been drawn this frame as a screenshot
+pub fn screenshot(ctx: &mut Context) -> GameResult<PathBuf> {
+    let (pixels, width, height) = read_screen(ctx);
+    if width == 0 || height == 0 {
+        return Err(GameError::RenderError("Nothing can be seen in the window".to_owned()))
+    }
+    save_png("screenshot", &pixels, width, height)
+}
@@ -0,0 +1,139 @@
+use ::*;
+use std::collections::HashMap;
+use std::io::Read;
+use ggez::GameError;
+use ggez::graphics::SpriteBatch;
+use super::camera::Camera;
+use super::starfield::{Starfield, StarLayer};
+
+/// Path of the file with the backgrounds
+const BACKGROUNDS_FILE: &str = "/backgrounds.toml";
+/// Name of the background used when a scenario doesn't pick one
+pub const DEFAULT_BACKGROUND: &str = "default";
+
+fn default_tint() -> [f32; 4] {
+    [1., 1., 1., 1.]
+}
+fn default_scale() -> f32 {
+    1.
+}
+fn default_tile() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Deserialize)]
+/// A picture behind the world that moves slower than it
+pub struct Layer {
+    /// Id of the sprite in the sprite manifest
+    pub sprite: String,
+    /// How much it moves and zooms with the camera, 0 is not at all and 1 is like the world
+    pub parallax: f32,
+    /// Colour multiplied onto the sprite
+    #[serde(default = "default_tint")]
+    pub tint: [f32; 4],
+    /// How much the sprite is scaled
+    #[serde(default = "default_scale")]
+    pub scale: f32,
+    /// Whether it's repeated to cover the whole screen
+    #[serde(default = "default_tile")]
+    pub tile: bool,
+    /// Where it is in the layer if it isn't repeated
+    #[serde(default)]
+    pub pos: [f32; 2],
+    /// Batch the layer is drawn with, kept between frames
+    #[serde(skip)]
+    batch: Option<SpriteBatch>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+/// Layers drawn from back to front
+pub struct Background {
+    /// The name scenarios use for it
+    pub name: String,
+    /// The layers with the furthest away first
+    #[serde(default)]
+    pub layers: Vec<Layer>,
+    /// Layers of generated stars drawn in front of the other layers
+    #[serde(default)]
+    pub stars: Vec<StarLayer>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BackgroundsFile {
+    backgrounds: Vec<Background>,
+}
+
+/// Loads all the backgrounds by their names
+///
+/// All the sprites they use have to be in the assets
+pub fn load_backgrounds(ctx: &mut Context, assets: &Assets) -> GameResult<HashMap<String, Background>> {
+    let mut file = ctx.filesystem.open(BACKGROUNDS_FILE)?;
+    let mut s = String::new();
+    file.read_to_string(&mut s)?;
+
+    let backgrounds_file: BackgroundsFile = ::toml::from_str(&s)?;
+    let mut backgrounds = HashMap::new();
+    for bg in backgrounds_file.backgrounds {
+        if let Some(layer) = bg.layers.iter().find(|layer| assets.find(&layer.sprite).is_none()) {
+            return Err(GameError::ResourceLoadError(format!("Background `{}` uses the unknown sprite `{}`", bg.name, layer.sprite)))
+        }
+        backgrounds.insert(bg.name.clone(), bg);
+    }
+    Ok(backgrounds)
+}
+
+impl Background {
+    /// Draws the layers as seen through the camera, with the stars generated from `seed`
+    pub fn draw(&mut self, ctx: &mut Context, assets: &Assets, starfield: &mut Starfield, camera: &Camera, seed: u64) -> GameResult<()> {
+        for layer in &mut self.layers {
+            let (info, img) = match assets.find(&layer.sprite) {
+                Some(sprite) => sprite,
+                None => continue,
+            };
+            let view = camera.parallax(layer.parallax);
+            let src = info.src(img, 0);
+            let t = layer.tint;
+            let param = graphics::DrawParam {
+                src,
+                scale: Point2::new(layer.scale, layer.scale),
+                color: Some(Color{r:t[0],g:t[1],b:t[2],a:t[3]}),
+                .. Default::default()
+            };
+
+            // The sprite of the layer can be changed when the assets are reloaded, so the image is given when drawing
+            let batch = layer.batch.get_or_insert_with(|| SpriteBatch::new(img.clone()));
+            batch.clear();
+            if layer.tile {
+                let (w, h) = (info.width * layer.scale, info.height * layer.scale);
+                if w <= 0. || h <= 0. {
+                    continue
+                }
+                // Just enough copies to cover what can be seen of the layer
+                let rect = view.view_rect();
+                let (x0, x1) = ((rect.x / w).floor() as i32, ((rect.x + rect.w) / w).ceil() as i32);
+                let (y0, y1) = ((rect.y / h).floor() as i32, ((rect.y + rect.h) / h).ceil() as i32);
+                for i in x0..x1 {
+                    for j in y0..y1 {
+                        batch.add(graphics::DrawParam {
+                            dest: Point2::new(i as f32 * w, j as f32 * h),
+                            .. param
+                        });
+                    }
+                }
+            } else {
+                batch.add(graphics::DrawParam {
+                    dest: Point2::new(layer.pos[0], layer.pos[1]),
+                    offset: info.offset(),
+                    .. param
+                });
+            }
+
+            graphics::push_transform(ctx, Some(view.transform()));
+            graphics::apply_transformations(ctx)?;
+            graphics::draw(ctx, &batch.with_image(img), Point2::origin(), 0.)?;
+            graphics::pop_transform(ctx);
+            graphics::apply_transformations(ctx)?;
+        }
+        starfield.draw(ctx, &self.stars, camera, seed)
+    }
+}
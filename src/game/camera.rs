@@ -0,0 +1,172 @@
+use std::f32::consts::PI;
+
+use ::*;
+use ggez::nalgebra as na;
+use ggez::graphics::Rect;
+
+/// How quickly the camera catches up with what it follows (angular frequency of the spring)
+const STIFFNESS: f32 = 6.;
+/// How many seconds of velocity the camera looks ahead by
+const LOOK_AHEAD: f32 = 0.6;
+/// Largest look-ahead as a fraction of the smallest side of the screen
+const MAX_LOOK_AHEAD: f32 = 0.3;
+/// How quickly the camera turns with the ship
+const TURN_RATE: f32 = 5.;
+/// Furthest the camera can zoom out
+const MIN_ZOOM: f32 = 0.25;
+/// Furthest the camera can zoom in
+const MAX_ZOOM: f32 = 2.;
+/// How fast the spectator camera moves in screen pixels per second
+const PAN_SPEED: f32 = 600.;
+
+#[derive(Debug, Clone)]
+/// Decides which part of the world is seen
+pub struct Camera {
+    /// The point in the world at the centre of the screen
+    pub pos: Point2,
+    /// Velocity of the camera itself
+    vel: Vector2,
+    /// How many screen pixels a world pixel is
+    pub zoom: f32,
+    /// Rotation of the view in radians
+    pub rot: f32,
+    /// Whether the view turns so the ship always points up
+    pub rotate_with_ship: bool,
+    /// Whether the camera is moved by hand instead of following the ship
+    pub free: bool,
+    width: f32,
+    height: f32,
+    /// How far the centre of the screen is from the centre of the whole picture when only part of it is drawn
+    offset: Vector2,
+}
+
+impl Camera {
+    /// Makes a camera looking at `pos` on a screen of the given size
+    pub fn new(pos: Point2, width: u32, height: u32) -> Self {
+        Camera {
+            pos,
+            vel: na::zero(),
+            zoom: 1.,
+            rot: 0.,
+            rotate_with_ship: false,
+            free: false,
+            width: width as f32,
+            height: height as f32,
+            offset: na::zero(),
+        }
+    }
+    /// Changes the size of the screen so the view stays centred
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.width = width as f32;
+        self.height = height as f32;
+    }
+    /// Moves the camera one tick towards following an object
+    ///
+    /// Uses a critically damped spring so it never overshoots
+    pub fn follow(&mut self, obj: &Object) {
+        if self.free {
+            return
+        }
+        let max_ahead = MAX_LOOK_AHEAD * self.width.min(self.height) / self.zoom;
+        let mut ahead = LOOK_AHEAD * obj.vel;
+        if ahead.norm() > max_ahead {
+            ahead *= max_ahead / ahead.norm();
+        }
+        let goal = obj.pos + ahead;
+
+        // The velocity of the object is added so the spring only has to handle the difference
+        let acc = STIFFNESS * STIFFNESS * (goal - self.pos) - 2. * STIFFNESS * (self.vel - obj.vel);
+        self.vel += acc * DELTA;
+        self.pos += self.vel * DELTA;
+
+        let goal_rot = if self.rotate_with_ship { -0.5 * PI - obj.rot } else { 0. };
+        self.rot += angle_diff(goal_rot, self.rot) * (TURN_RATE * DELTA).min(1.);
+        self.rot %= 2. * PI;
+    }
+    /// Moves the spectator camera one tick in a direction on the screen
+    pub fn pan(&mut self, dir: Vector2) {
+        if self.free {
+            self.vel = na::zero();
+            let world_dir = rotate(dir, -self.rot);
+            self.pos += PAN_SPEED / self.zoom * DELTA * world_dir;
+        }
+    }
+    /// Jumps straight to a point
+    pub fn snap_to(&mut self, pos: Point2) {
+        self.pos = pos;
+        self.vel = na::zero();
+    }
+    /// Zooms in by a factor (out if it's less than 1) within the limits
+    pub fn zoom_by(&mut self, factor: f32) {
+        self.zoom = (self.zoom * factor).max(MIN_ZOOM).min(MAX_ZOOM);
+    }
+    /// The centre of the screen
+    pub fn screen_center(&self) -> Point2 {
+        Point2::new(self.width / 2., self.height / 2.)
+    }
+    /// The camera seen from a layer further away, which moves and zooms less
+    ///
+    /// A `factor` of 0 doesn't move at all and 1 moves like the world
+    pub fn parallax(&self, factor: f32) -> Camera {
+        let zoom = self.zoom.powf(factor);
+        // The layers move with the centre of the whole picture, not with the part of it being drawn
+        let center = self.pos - self.to_world_vec(self.offset);
+        Camera {
+            pos: Point2::origin() + factor * center.coords + rotate(self.offset, -self.rot) / zoom,
+            zoom,
+            .. self.clone()
+        }
+    }
+    /// The camera that shows what is `offset` from the centre of the screen, as if the screen was bigger
+    ///
+    /// Used to draw a picture larger than the screen a screen at a time
+    pub fn section(&self, offset: Vector2) -> Camera {
+        Camera {
+            pos: self.pos + self.to_world_vec(offset),
+            offset: self.offset + offset,
+            .. self.clone()
+        }
+    }
+    /// The transformation from world to screen coordinates
+    pub fn transform(&self) -> Matrix4 {
+        let center = self.screen_center().coords;
+        Matrix4::new_translation(&center.fixed_resize(0.))
+            * Matrix4::new_rotation(na::Vector3::new(0., 0., self.rot))
+            * Matrix4::new_scaling(self.zoom)
+            * Matrix4::new_translation(&(-self.pos.coords).fixed_resize(0.))
+    }
+    /// Converts a point in the world to a point on the screen
+    pub fn to_screen(&self, p: Point2) -> Point2 {
+        self.screen_center() + self.zoom * rotate(p - self.pos, self.rot)
+    }
+    /// Converts a point on the screen to a point in the world
+    pub fn to_world(&self, p: Point2) -> Point2 {
+        self.pos + rotate(p - self.screen_center(), -self.rot) / self.zoom
+    }
+    /// The part of the world that can be seen
+    ///
+    /// When the view is rotated this is the box around the rotated screen
+    pub fn view_rect(&self) -> Rect {
+        let corners = [
+            self.to_world(Point2::new(0., 0.)),
+            self.to_world(Point2::new(self.width, 0.)),
+            self.to_world(Point2::new(0., self.height)),
+            self.to_world(Point2::new(self.width, self.height)),
+        ];
+        let min_x = corners.iter().map(|p| p.x).fold(::std::f32::INFINITY, f32::min);
+        let min_y = corners.iter().map(|p| p.y).fold(::std::f32::INFINITY, f32::min);
+        let max_x = corners.iter().map(|p| p.x).fold(::std::f32::NEG_INFINITY, f32::max);
+        let max_y = corners.iter().map(|p| p.y).fold(::std::f32::NEG_INFINITY, f32::max);
+        Rect::new(min_x, min_y, max_x - min_x, max_y - min_y)
+    }
+    /// Converts a vector on the screen to one in the world
+    pub fn to_world_vec(&self, v: Vector2) -> Vector2 {
+        rotate(v, -self.rot) / self.zoom
+    }
+}
+
+/// Rotates a vector by an angle
+fn rotate(v: Vector2, angle: f32) -> Vector2 {
+    let (sin, cos) = angle.sin_cos();
+    Vector2::new(cos * v.x - sin * v.y, sin * v.x + cos * v.y)
+}
@@ -0,0 +1,87 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use ::*;
+use super::world::{World, DrawStats};
+
+/// How many frames the frame time graph shows
+const GRAPH_FRAMES: usize = 120;
+/// Width of the graph in pixels
+const GRAPH_WIDTH: f32 = 240.;
+/// Height of the graph in pixels
+const GRAPH_HEIGHT: f32 = 60.;
+/// Frame time at the top of the graph in milliseconds
+const GRAPH_MAX_MS: f32 = 50.;
+
+/// Colour of the frame time graph
+const GRAPH: Color = Color{r:0.3,g:1.,b:1.,a:0.8};
+
+/// Shows how fast the game is running and what's in the world
+pub struct DebugOverlay {
+    /// Whether it's shown
+    pub visible: bool,
+    frame_times: VecDeque<f32>,
+    ticks: u32,
+    physics_time: Duration,
+    draw_time: Duration,
+    collision_pairs: usize,
+}
+
+/// Duration in milliseconds
+fn ms(d: Duration) -> f32 {
+    (timer::duration_to_f64(d) * 1000.) as f32
+}
+
+impl Default for DebugOverlay {
+    /// A hidden overlay
+    fn default() -> Self {
+        DebugOverlay {
+            visible: false,
+            frame_times: VecDeque::with_capacity(GRAPH_FRAMES),
+            ticks: 0,
+            physics_time: Duration::from_secs(0),
+            draw_time: Duration::from_secs(0),
+            collision_pairs: 0,
+        }
+    }
+}
+
+impl DebugOverlay {
+    /// Records an update
+    ///
+    /// `ticks` physics ticks took `physics_time` and tested `collision_pairs` pairs in total
+    pub fn record_update(&mut self, ctx: &Context, ticks: u32, physics_time: Duration, collision_pairs: usize) {
+        self.ticks = ticks;
+        self.physics_time = physics_time;
+        self.collision_pairs = collision_pairs;
+
+        if self.frame_times.len() == GRAPH_FRAMES {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(ms(timer::get_delta(ctx)));
+    }
+    /// Records how long drawing the last frame took
+    pub fn record_draw(&mut self, draw_time: Duration) {
+        self.draw_time = draw_time;
+    }
+    /// Makes the lines and the frame time graph in the current panel of the UI if it's shown
+    pub fn panel(&self, ui: &mut Ui, ctx: &mut Context, world: &World, draw_stats: DrawStats, particles: usize) -> GameResult<()> {
+        if !self.visible {
+            return Ok(())
+        }
+        let (momentum, energy) = world.momentum_and_energy();
+        ui.space(8.);
+        ui.label(ctx, &format!("FPS: {:5.1}  Ticks this frame: {}", timer::get_fps(ctx), self.ticks))?;
+        ui.label(ctx, &format!("Physics: {:5.2} ms  Draw: {:5.2} ms", ms(self.physics_time), ms(self.draw_time)))?;
+        ui.label(ctx, &format!("Asteroids: {}  Pickups: {}  Bullets: {}  Particles: {}",
+            world.asteroids.len(), world.pickups.len(), world.bullets.len(), particles))?;
+        ui.label(ctx, &format!("Drawn: {}  Culled: {}", draw_stats.drawn, draw_stats.culled))?;
+        ui.label(ctx, &format!("Collision pairs: {}  Seed: {}", self.collision_pairs, world.field.seed()))?;
+        ui.label(ctx, &format!("Momentum: ({:.3e}, {:.3e})  Energy: {:.3e}", momentum.x, momentum.y, energy))?;
+
+        // Graph of the frame times with the newest to the right and a line at the frame time of the desired FPS
+        let frame_times: Vec<_> = self.frame_times.iter().cloned().collect();
+        ui.graph(&frame_times, GRAPH_MAX_MS, GRAPH_WIDTH, GRAPH_HEIGHT, GRAPH, &[DELTA * 1000.]);
+        Ok(())
+    }
+}
@@ -0,0 +1,77 @@
+use ::*;
+
+/// Names of the sizes in the order of `Size::ALL`
+const SIZE_NAMES: [&str; 3] = ["Small", "Medium", "Large"];
+/// Names of the materials in the order of `Material::ALL`
+const MATERIAL_NAMES: [&str; 3] = ["Ice", "Rock", "Metal"];
+/// Least and most litres of fuel a spawned pickup can have
+const FUEL_RANGE: (f32, f32) = (50., 1000.);
+/// Width of the fuel slider
+const SLIDER_WIDTH: f32 = 150.;
+
+/// What is spawned with the mouse, picked in a panel of the HUD
+///
+/// Asteroids are spawned with the left button and fuel with the right
+pub struct SpawnEditor {
+    /// Whether the panel is shown
+    pub visible: bool,
+    /// Index of the size of asteroids in `Size::ALL`
+    size: usize,
+    /// Index of the material of asteroids in `Material::ALL`
+    material: usize,
+    /// Litres of fuel in pickups
+    fuel: f32,
+}
+
+impl Default for SpawnEditor {
+    /// A hidden editor spawning medium rock asteroids and 200 L of fuel
+    fn default() -> Self {
+        SpawnEditor {
+            visible: false,
+            size: 1,
+            material: 1,
+            fuel: 200.,
+        }
+    }
+}
+
+impl SpawnEditor {
+    /// Makes an asteroid with the picked size and material
+    pub fn asteroid(&self, p: Point2) -> Asteroid {
+        Asteroid::new(p, Size::ALL[self.size], Material::ALL[self.material])
+    }
+    /// Makes a fuel pickup with the picked amount
+    pub fn fuel(&self, p: Point2) -> Pickup {
+        Pickup {
+            amount: self.fuel.round(),
+            .. make_fuel(p)
+        }
+    }
+    /// Makes the panel in the bottom left corner of the UI if it's shown
+    pub fn panel(&mut self, ui: &mut Ui, ctx: &mut Context) -> GameResult<()> {
+        if !self.visible {
+            return Ok(())
+        }
+        ui.begin_panel(Anchor::BottomLeft, 10.);
+        ui.label(ctx, "SPAWN EDITOR (F2)")?;
+        ui.space(8.);
+
+        // The highlight of the lists follows the mouse, so what's picked is written above them
+        ui.label(ctx, &format!("Left button: {} {} asteroid",
+            SIZE_NAMES[self.size], MATERIAL_NAMES[self.material].to_lowercase()))?;
+        let mut hovered = self.size;
+        if let Some(i) = ui.list(ctx, &SIZE_NAMES[..], &mut hovered)? {
+            self.size = i;
+        }
+        ui.space(8.);
+        let mut hovered = self.material;
+        if let Some(i) = ui.list(ctx, &MATERIAL_NAMES[..], &mut hovered)? {
+            self.material = i;
+        }
+        ui.space(8.);
+
+        let label = format!("Right button: {:.0} L of fuel", self.fuel);
+        ui.slider(ctx, &label, &mut self.fuel, FUEL_RANGE.0, FUEL_RANGE.1, SLIDER_WIDTH)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,249 @@
+use ::*;
+use ggez::nalgebra as na;
+use std::collections::{HashMap, HashSet};
+use super::world::Objects;
+use rng::Rng;
+
+/// Width and height of a chunk
+pub const CHUNK_SIZE: f32 = 1000.;
+/// Chunks this many chunks away from the camera get loaded
+const LOAD_DISTANCE: i32 = 1;
+/// Chunks further than this many chunks away from the camera get frozen
+const UNLOAD_DISTANCE: i32 = 2;
+
+/// Coordinates of a chunk
+pub type ChunkPos = (i32, i32);
+
+/// The chunk a point is in
+pub fn chunk_of(p: Point2) -> ChunkPos {
+    ((p.x / CHUNK_SIZE).floor() as i32, (p.y / CHUNK_SIZE).floor() as i32)
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+/// Objects of a chunk that isn't loaded
+pub struct FrozenChunk {
+    asteroids: Vec<Asteroid>,
+    pickups: Vec<Pickup>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+/// Generates the asteroid field in chunks around the camera
+///
+/// Chunks are generated from the seed the first time they're needed.
+/// When they get far away, their objects are taken out of the world and frozen
+/// until the camera comes back.
+pub struct Field {
+    seed: u64,
+    loaded: HashSet<ChunkPos>,
+    frozen: HashMap<ChunkPos, FrozenChunk>,
+}
+
+impl Field {
+    /// Make a new field where nothing has been generated yet
+    pub fn new(seed: u64) -> Self {
+        Field {
+            seed,
+            loaded: HashSet::new(),
+            frozen: HashMap::new(),
+        }
+    }
+    /// The seed the chunks are generated from
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+    /// Loads the chunks near `center` and freezes the ones far away
+    pub(super) fn update(&mut self, center: Point2, asteroids: &mut Objects<Asteroid>, pickups: &mut Objects<Pickup>) {
+        let (cx, cy) = chunk_of(center);
+
+        // Load chunks that have come close
+        for x in cx - LOAD_DISTANCE..cx + LOAD_DISTANCE + 1 {
+            for y in cy - LOAD_DISTANCE..cy + LOAD_DISTANCE + 1 {
+                let c = (x, y);
+                if self.loaded.insert(c) {
+                    let seed = self.seed;
+                    let chunk = self.frozen.remove(&c).unwrap_or_else(|| generate(seed, c));
+                    asteroids.extend(chunk.asteroids);
+                    pickups.extend(chunk.pickups);
+                }
+            }
+        }
+
+        // Forget chunks that are far away
+        // They're frozen even if they're empty, so what was taken from them doesn't come back
+        let far: Vec<ChunkPos> = self.loaded.iter()
+            .cloned()
+            .filter(|&(x, y)| (x - cx).abs() > UNLOAD_DISTANCE || (y - cy).abs() > UNLOAD_DISTANCE)
+            .collect();
+        for c in far {
+            self.loaded.remove(&c);
+            self.frozen.entry(c).or_insert_with(FrozenChunk::default);
+        }
+
+        // Freeze everything that isn't in a loaded chunk anymore
+        // Mission targets are kept so they can't disappear
+        let (asteroids_out, pickups_out) = {
+            let loaded = &self.loaded;
+            (take_where(asteroids, |ast| !ast.target && !loaded.contains(&chunk_of(ast.pos))),
+            take_where(pickups, |pickup| !loaded.contains(&chunk_of(pickup.obj.pos))))
+        };
+
+        for ast in asteroids_out {
+            self.frozen_chunk(chunk_of(ast.pos)).asteroids.push(ast);
+        }
+        for pickup in pickups_out {
+            self.frozen_chunk(chunk_of(pickup.obj.pos)).pickups.push(pickup);
+        }
+    }
+    /// Gets a frozen chunk, generating it first if it has never been visited
+    fn frozen_chunk(&mut self, c: ChunkPos) -> &mut FrozenChunk {
+        let seed = self.seed;
+        self.frozen.entry(c).or_insert_with(|| generate(seed, c))
+    }
+}
+
+/// Takes the objects out of the collection that match the predicate
+fn take_where<T: AsObject, F: FnMut(&T) -> bool>(objs: &mut Objects<T>, mut f: F) -> Vec<T> {
+    let all = ::std::mem::replace(&mut **objs, Vec::new());
+    let (taken, kept): (Vec<T>, Vec<T>) = all.into_iter().partition(|obj| f(obj));
+    **objs = kept;
+    taken
+}
+
+/// Generates the contents of a chunk from the seed
+///
+/// The same seed and chunk always give the same contents
+pub fn generate(seed: u64, c: ChunkPos) -> FrozenChunk {
+    let mut chunk = FrozenChunk::default();
+
+    // Keep the chunk the player starts in empty
+    if c == (0, 0) {
+        return chunk;
+    }
+
+    let mut rng = Rng::with_coords(seed, c.0, c.1);
+    let origin = Point2::new(c.0 as f32 * CHUNK_SIZE, c.1 as f32 * CHUNK_SIZE);
+    // Keep clusters away from the edges so they stay in their chunk
+    let center = origin + Vector2::new(rng.range(250., CHUNK_SIZE - 250.), rng.range(250., CHUNK_SIZE - 250.));
+    // The whole cluster drifts the same way
+    let drift = Vector2::new(rng.range(-15., 15.), rng.range(-15., 15.));
+
+    let roll = rng.next_f32();
+    if roll < 0.45 {
+        // Empty region
+    } else if roll < 0.85 {
+        // Asteroid cluster
+        let count = rng.range_u32(3, 9);
+        for _ in 0..count {
+            let p = scatter(&mut rng, center, 200.);
+            let size = Size::ALL[rng.range_u32(0, 3) as usize];
+            let material = random_material(&mut rng);
+            if chunk.asteroids.iter().any(|ast| na::distance(&ast.pos, &p) < ast.rad + size.radius()) {
+                continue
+            }
+            let mut ast = Asteroid::new(p, size, material);
+            ast.vel = drift + Vector2::new(rng.range(-5., 5.), rng.range(-5., 5.));
+            ast.rot = rng.range(0., 2. * ::std::f32::consts::PI);
+            chunk.asteroids.push(ast);
+        }
+    } else {
+        // Fuel cache, sometimes with other supplies
+        let count = rng.range_u32(2, 5);
+        for _ in 0..count {
+            let p = scatter(&mut rng, center, 80.);
+            if chunk.pickups.iter().any(|pickup| na::distance(&pickup.obj.pos, &p) < 2. * pickup.obj.rad) {
+                continue
+            }
+            let mut pickup = random_pickup(&mut rng, p);
+            pickup.obj.vel = drift;
+            chunk.pickups.push(pickup);
+        }
+    }
+
+    chunk
+}
+
+/// Picks a material where rock is the most common and metal the rarest
+fn random_material(rng: &mut Rng) -> Material {
+    let roll = rng.next_f32();
+    if roll < 0.3 {
+        Material::Ice
+    } else if roll < 0.85 {
+        Material::Rock
+    } else {
+        Material::Metal
+    }
+}
+
+/// Makes a pickup that's most likely fuel
+fn random_pickup(rng: &mut Rng, p: Point2) -> Pickup {
+    let roll = rng.next_f32();
+    if roll < 0.7 {
+        Pickup::new(p, PickupKind::Fuel, rng.range(100., 300.).round())
+    } else if roll < 0.8 {
+        Pickup::new(p, PickupKind::Repair, rng.range(5., 20.).round())
+    } else if roll < 0.9 {
+        Pickup::new(p, PickupKind::Ammo, rng.range(20., 50.).round())
+    } else if roll < 0.97 {
+        Pickup::new(p, PickupKind::Score, 100.)
+    } else {
+        Pickup::new(p, PickupKind::Weapon(Weapon::Spread), 1.)
+    }
+}
+
+/// A random point at most `spread` away from `center`
+fn scatter(rng: &mut Rng, center: Point2, spread: f32) -> Point2 {
+    let dist = spread * rng.next_f32().sqrt();
+    center + dist * angle_to_vec(rng.range(0., 2. * ::std::f32::consts::PI))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEED: u64 = 42;
+
+    fn positions(chunk: &FrozenChunk) -> Vec<(f32, f32)> {
+        chunk.asteroids.iter().map(|ast| (ast.pos.x, ast.pos.y))
+            .chain(chunk.pickups.iter().map(|pickup| (pickup.obj.pos.x, pickup.obj.pos.y)))
+            .collect()
+    }
+    fn chunk_center(c: ChunkPos) -> Point2 {
+        Point2::new((c.0 as f32 + 0.5) * CHUNK_SIZE, (c.1 as f32 + 0.5) * CHUNK_SIZE)
+    }
+
+    #[test]
+    fn generation_is_deterministic() {
+        for x in -3..4 {
+            for y in -3..4 {
+                assert_eq!(positions(&generate(SEED, (x, y))), positions(&generate(SEED, (x, y))));
+            }
+        }
+        // Different seeds give different fields
+        let all = |seed| (1..20).flat_map(|x| positions(&generate(seed, (x, 0)))).collect::<Vec<_>>();
+        assert_ne!(all(SEED), all(SEED + 1));
+    }
+
+    #[test]
+    fn emptied_chunk_stays_empty() {
+        // A chunk with something in it
+        let c = (1..100).map(|x| (x, 0)).find(|&c| !positions(&generate(SEED, c)).is_empty()).unwrap();
+
+        let mut field = Field::new(SEED);
+        let mut asteroids = Objects::new(Vec::new(), Sprite::Asteroid);
+        let mut pickups = Objects::new(Vec::new(), Sprite::Fuel);
+        field.update(chunk_center(c), &mut asteroids, &mut pickups);
+        assert!(!asteroids.is_empty() || !pickups.is_empty());
+
+        // Take everything, fly away and come back
+        asteroids.clear();
+        pickups.clear();
+        field.update(chunk_center((c.0 + 10, c.1)), &mut asteroids, &mut pickups);
+        asteroids.clear();
+        pickups.clear();
+        field.update(chunk_center(c), &mut asteroids, &mut pickups);
+
+        let in_chunk = asteroids.iter().filter(|ast| chunk_of(ast.pos) == c).count()
+            + pickups.iter().filter(|pickup| chunk_of(pickup.obj.pos) == c).count();
+        assert_eq!(in_chunk, 0);
+    }
+}
@@ -0,0 +1,129 @@
+use ::*;
+use ggez::nalgebra as na;
+
+/// Seconds the health bar flashes for after taking damage
+const FLASH_TIME: f32 = 0.6;
+/// Times per second the health bar flashes
+const FLASH_RATE: f32 = 8.;
+/// Width of the gauges
+const GAUGE_WIDTH: f32 = 200.;
+/// Width of the gauges when the HUD is compact
+const COMPACT_WIDTH: f32 = 100.;
+/// Fuel running out sooner than this many seconds is shown as low
+const LOW_FUEL_TIME: f64 = 15.;
+/// Fraction of a full tank that is low
+const LOW_FUEL: f64 = 0.1;
+
+#[derive(Debug, Copy, Clone)]
+/// The colours of the HUD
+pub struct Theme {
+    /// Colour of the text
+    pub text: Color,
+    /// Colour of the fuel bar
+    pub fuel: Color,
+    /// Colour of the fuel bar when it's running out
+    pub low: Color,
+    /// Colour of the throttle bar
+    pub throttle: Color,
+    /// Colour of the health bar
+    pub health: Color,
+    /// Colour the health bar flashes with after taking damage
+    pub flash: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            text: Color{r:1.,g:1.,b:1.,a:1.},
+            fuel: Color{r:0.3,g:0.8,b:1.,a:1.},
+            low: Color{r:1.,g:0.6,b:0.1,a:1.},
+            throttle: Color{r:1.,g:0.85,b:0.3,a:1.},
+            health: Color{r:0.3,g:0.9,b:0.3,a:1.},
+            flash: Color{r:1.,g:0.2,b:0.2,a:1.},
+        }
+    }
+}
+
+/// Gauges showing the state of the player's ship
+pub struct Hud {
+    /// Whether only the bars are shown
+    pub compact: bool,
+    /// The colours used
+    pub theme: Theme,
+    last_health: f32,
+    /// Seconds left of flashing
+    flash: f32,
+}
+
+/// Direction of a rotation as a compass heading in degrees, with 0 being up the screen
+fn heading(rot: f32) -> f32 {
+    let deg = (rot + ::std::f32::consts::FRAC_PI_2).to_degrees();
+    (deg % 360. + 360.) % 360.
+}
+
+impl Hud {
+    /// Makes an expanded HUD with the default theme
+    pub fn new(player: &ThrustedObj) -> Self {
+        Hud {
+            compact: false,
+            theme: Theme::default(),
+            last_health: player.health,
+            flash: 0.,
+        }
+    }
+    /// Notices damage taken by the player this tick
+    pub fn tick(&mut self, player: &ThrustedObj) {
+        if player.health < self.last_health {
+            self.flash = FLASH_TIME;
+        }
+        self.last_health = player.health;
+        self.flash = (self.flash - DELTA).max(0.);
+    }
+    /// Makes the gauges in the current panel of the UI
+    pub fn gauges(&self, ui: &mut Ui, ctx: &mut Context, player: &ThrustedObj) -> GameResult<()> {
+        let theme = &self.theme;
+        let thruster = &player.thruster;
+        let width = if self.compact { COMPACT_WIDTH } else { GAUGE_WIDTH };
+
+        // Fuel and how long it lasts at the current throttle
+        let burning = thruster.power && thruster.throttle_usage > 0.;
+        let time_left = if burning { Some(thruster.fuel / thruster.throttle_usage) } else { None };
+        let low = thruster.fuel < LOW_FUEL * PLAYER_FUEL || time_left.map(|t| t < LOW_FUEL_TIME).unwrap_or(false);
+        if !self.compact {
+            let fuel_str = match time_left {
+                Some(t) => format!("Fuel: {:7.1} L  Empty in {:4.0} s", thruster.fuel, t),
+                None => format!("Fuel: {:7.1} L", thruster.fuel),
+            };
+            ui.label_colored(ctx, &fuel_str, if low { theme.low } else { theme.text })?;
+        }
+        let fuel_color = if low { theme.low } else { theme.fuel };
+        ui.progress((thruster.fuel / PLAYER_FUEL) as f32, width, fuel_color, &[]);
+
+        // Throttle with lines where the flame gets bigger
+        let max = thruster.max_throttle();
+        if !self.compact {
+            let state = if thruster.power { "" } else { " (off)" };
+            ui.label_colored(ctx, &format!("Throttle: {:4.1} L/s{}", thruster.throttle_usage, state), theme.text)?;
+        }
+        let marks: Vec<f32> = THROTTLE_STEPS.iter().map(|&step| (step / max) as f32).collect();
+        ui.progress((thruster.throttle_usage / max) as f32, width, theme.throttle, &marks);
+
+        // Health, flashing after taking damage
+        let flashing = self.flash > 0. && (self.flash * FLASH_RATE) as u32 % 2 == 0;
+        if !self.compact {
+            ui.label_colored(ctx, &format!("Health: {:3.0}", player.health), theme.text)?;
+        }
+        let health_color = if flashing { theme.flash } else { theme.health };
+        ui.progress(player.health / PLAYER_HEALTH, width, health_color, &[]);
+
+        // Speed and heading
+        let speed = na::norm(&player.vel);
+        if self.compact {
+            ui.label_colored(ctx, &format!("{:5.0} px/s {:03.0}°", speed, heading(player.rot)), theme.text)?;
+        } else {
+            ui.label_colored(ctx, &format!("Speed: {:5.0} px/s  Heading: {:03.0}°", speed, heading(player.rot)), theme.text)?;
+            ui.label_colored(ctx, &format!("Delta-v: {:5.0} px/s", player.delta_v()), theme.text)?;
+        }
+        Ok(())
+    }
+}
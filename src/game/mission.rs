@@ -0,0 +1,309 @@
+use ::*;
+use std::io::Read;
+use ggez::GameError;
+use ggez::nalgebra as na;
+use super::world::Objects;
+use super::score::Score;
+
+/// Path of the file with all the missions
+const MISSIONS_FILE: &str = "/missions.toml";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+/// The kinds of objectives
+pub enum ObjectiveKind {
+    /// Fly within `radius` of `pos`
+    ReachWaypoint,
+    /// Collect `count` fuel canisters
+    CollectFuel,
+    /// Destroy a target asteroid spawned at `pos` with `health` hit points
+    DestroyTarget,
+    /// Stay alive for `time` seconds
+    Survive,
+    /// Pick up cargo within `radius` of `from` and bring it within `radius` of `pos`
+    DeliverCargo,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A single goal of a mission
+///
+/// Which of the fields are used depends on the `kind`
+pub struct Objective {
+    /// What has to be done
+    pub kind: ObjectiveKind,
+    /// Text shown in the HUD
+    pub description: String,
+    #[serde(default)]
+    /// Position of the waypoint, target or cargo destination
+    pub pos: (f32, f32),
+    #[serde(default)]
+    /// Position where cargo is picked up
+    pub from: (f32, f32),
+    #[serde(default = "default_radius")]
+    /// How close the ship has to get to positions
+    pub radius: f32,
+    #[serde(default)]
+    /// Amount of things to collect
+    pub count: u32,
+    #[serde(default)]
+    /// Time to survive in seconds
+    pub time: f32,
+    #[serde(default = "default_health")]
+    /// Hit points of the target
+    pub health: f32,
+    #[serde(default)]
+    /// Seconds before the objective fails
+    pub time_limit: Option<f32>,
+}
+
+fn default_radius() -> f32 {
+    50.
+}
+fn default_health() -> f32 {
+    100.
+}
+
+impl Objective {
+    fn pos(&self) -> Point2 {
+        Point2::new(self.pos.0, self.pos.1)
+    }
+    fn from_pos(&self) -> Point2 {
+        Point2::new(self.from.0, self.from.1)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A chain of objectives that have to be completed in order
+pub struct Mission {
+    /// Name of the mission
+    pub name: String,
+    /// The objectives
+    pub objectives: Vec<Objective>,
+    /// Name of the background shown during the mission
+    #[serde(default)]
+    pub background: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MissionsFile {
+    missions: Vec<Mission>,
+}
+
+/// Loads all missions from the missions file in the resources
+pub fn load_missions(ctx: &mut Context) -> GameResult<Vec<Mission>> {
+    let mut file = ctx.filesystem.open(MISSIONS_FILE)?;
+    let mut s = String::new();
+    file.read_to_string(&mut s)?;
+
+    parse_missions(&s)
+}
+
+/// Parses the text of a missions file
+///
+/// Fails if a mission has no objectives
+fn parse_missions(s: &str) -> GameResult<Vec<Mission>> {
+    let missions_file: MissionsFile = ::toml::from_str(s)?;
+    if let Some(mission) = missions_file.missions.iter().find(|m| m.objectives.is_empty()) {
+        return Err(GameError::ResourceLoadError(format!("Mission `{}` in {} has no objectives", mission.name, MISSIONS_FILE)))
+    }
+    Ok(missions_file.missions)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// Whether a mission is still going
+pub enum MissionStatus {
+    /// Objectives are still left
+    Active,
+    /// All objectives were completed
+    Complete,
+    /// An objective failed
+    Failed,
+}
+
+#[derive(Debug, Clone)]
+/// Things that happen to the current mission
+pub enum MissionEvent {
+    /// An objective was completed, with its description
+    ObjectiveComplete(String),
+    /// The whole mission was completed, with its name
+    MissionComplete(String),
+    /// The mission failed because of the given reason
+    MissionFailed(String),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+/// A mission being played
+pub struct MissionProgress {
+    mission: Mission,
+    current: usize,
+    started: bool,
+    elapsed: f32,
+    fuel_at_start: u32,
+    carrying: bool,
+    status: MissionStatus,
+    #[serde(skip)]
+    events: Vec<MissionEvent>,
+}
+
+impl MissionProgress {
+    /// Starts a mission from the first objective
+    pub fn new(mission: Mission) -> Self {
+        MissionProgress {
+            mission,
+            current: 0,
+            started: false,
+            elapsed: 0.,
+            fuel_at_start: 0,
+            carrying: false,
+            status: MissionStatus::Active,
+            events: Vec::new(),
+        }
+    }
+    /// The status of the mission
+    pub fn status(&self) -> MissionStatus {
+        self.status
+    }
+    /// The objective currently being worked on
+    pub fn objective(&self) -> Option<&Objective> {
+        if self.status == MissionStatus::Active {
+            self.mission.objectives.get(self.current)
+        } else {
+            None
+        }
+    }
+    /// The background the mission asks for, if any
+    pub fn background(&self) -> Option<&str> {
+        self.mission.background.as_ref().map(|s| s.as_str())
+    }
+    /// Takes the events that happened since last time
+    pub fn take_events(&mut self) -> Vec<MissionEvent> {
+        ::std::mem::replace(&mut self.events, Vec::new())
+    }
+    fn fail(&mut self, reason: &str) {
+        self.status = MissionStatus::Failed;
+        self.events.push(MissionEvent::MissionFailed(reason.to_owned()));
+    }
+    /// Checks the current objective, should be run every physics update
+    pub(super) fn update(&mut self, player: &ThrustedObj, asteroids: &mut Objects<Asteroid>, score: &Score) {
+        if self.status != MissionStatus::Active {
+            return
+        }
+        if player.is_dead() {
+            return self.fail("The ship was destroyed");
+        }
+        let result = {
+            let obj = &self.mission.objectives[self.current];
+
+            // Set up the objective the first time it's checked
+            if !self.started {
+                self.started = true;
+                self.elapsed = 0.;
+                self.fuel_at_start = score.fuel_collected;
+                self.carrying = false;
+
+                if obj.kind == ObjectiveKind::DestroyTarget {
+                    let mut target = make_asteroid(obj.pos());
+                    target.health = obj.health;
+                    target.target = true;
+                    asteroids.push(target);
+                }
+            }
+            self.elapsed += DELTA;
+
+            let dist = |p: Point2| na::distance(&player.pos, &p);
+
+            if obj.time_limit.map_or(false, |limit| self.elapsed > limit) {
+                Err(format!("Ran out of time: {}", obj.description))
+            } else {
+                Ok(match obj.kind {
+                    ObjectiveKind::ReachWaypoint => dist(obj.pos()) <= obj.radius,
+                    ObjectiveKind::CollectFuel => score.fuel_collected - self.fuel_at_start >= obj.count,
+                    ObjectiveKind::DestroyTarget => !asteroids.iter().any(|ast| ast.target),
+                    ObjectiveKind::Survive => self.elapsed >= obj.time,
+                    ObjectiveKind::DeliverCargo => {
+                        if !self.carrying && dist(obj.from_pos()) <= obj.radius {
+                            self.carrying = true;
+                        }
+                        self.carrying && dist(obj.pos()) <= obj.radius
+                    }
+                })
+            }
+        };
+
+        let completed = match result {
+            Ok(completed) => completed,
+            Err(reason) => return self.fail(&reason),
+        };
+        if completed {
+            let description = self.mission.objectives[self.current].description.clone();
+            self.events.push(MissionEvent::ObjectiveComplete(description));
+            self.current += 1;
+            self.started = false;
+
+            if self.current >= self.mission.objectives.len() {
+                self.status = MissionStatus::Complete;
+                self.events.push(MissionEvent::MissionComplete(self.mission.name.clone()));
+            }
+        }
+    }
+    /// Where the ship should go for the current objective, if anywhere
+    pub fn target_pos(&self, asteroids: &Objects<Asteroid>) -> Option<Point2> {
+        self.objective().and_then(|obj| match obj.kind {
+            ObjectiveKind::ReachWaypoint => Some(obj.pos()),
+            ObjectiveKind::DestroyTarget => asteroids.iter().find(|ast| ast.target).map(|ast| ast.pos),
+            ObjectiveKind::DeliverCargo if !self.carrying => Some(obj.from_pos()),
+            ObjectiveKind::DeliverCargo => Some(obj.pos()),
+            ObjectiveKind::CollectFuel | ObjectiveKind::Survive => None,
+        })
+    }
+    /// Text describing the mission and the progress of the current objective
+    pub fn hud_text(&self, player: &ThrustedObj, score: &Score) -> String {
+        let obj = match self.objective() {
+            Some(obj) => obj,
+            None if self.status == MissionStatus::Failed => return format!("{}: Failed", self.mission.name),
+            None => return format!("{}: Complete", self.mission.name),
+        };
+        let dist = |p: Point2| na::distance(&player.pos, &p);
+
+        let progress = match obj.kind {
+            ObjectiveKind::ReachWaypoint => format!("{:.0} away", dist(obj.pos())),
+            ObjectiveKind::CollectFuel => {
+                // The objective hasn't been set up if it hasn't been checked yet
+                let collected = if self.started { score.fuel_collected - self.fuel_at_start } else { 0 };
+                format!("{}/{}", collected, obj.count)
+            }
+            ObjectiveKind::DestroyTarget => "target".to_owned(),
+            ObjectiveKind::Survive => format!("{:.0}/{:.0} s", self.elapsed, obj.time),
+            ObjectiveKind::DeliverCargo if !self.carrying => format!("pick up {:.0} away", dist(obj.from_pos())),
+            ObjectiveKind::DeliverCargo => format!("deliver {:.0} away", dist(obj.pos())),
+        };
+        let time_left = match obj.time_limit {
+            Some(limit) => format!(" [{:.0} s left]", (limit - self.elapsed).max(0.)),
+            None => String::new(),
+        };
+
+        format!("{} ({}/{}): {} ({}){}", self.mission.name, self.current + 1,
+            self.mission.objectives.len(), obj.description, progress, time_left)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missions_file_parses() {
+        let missions = parse_missions(include_str!("../../resources/missions.toml")).unwrap();
+        assert!(!missions.is_empty());
+    }
+
+    #[test]
+    fn mission_without_objectives() {
+        let s = "[[missions]]\nname = \"Empty\"\nobjectives = []\n";
+        match parse_missions(s) {
+            Err(GameError::ResourceLoadError(e)) => assert!(e.contains("`Empty`"), "{}", e),
+            Err(e) => panic!("wrong error: {}", e),
+            Ok(_) => panic!("no error"),
+        }
+    }
+}
@@ -1,174 +1,425 @@
 use ::*;
+use ggez::GameError;
+use ggez::nalgebra as na;
+use ggez::graphics::{Rect, SpriteBatch};
 
 /// Stuff related to things in the world
 pub mod world;
+/// Score keeping and the high-score table
+pub mod score;
+/// Missions made of chained objectives
+pub mod mission;
+/// Procedural generation of the asteroid field
+pub mod gen;
+/// Selecting objects and their motion relative to the player
+pub mod target;
+/// Predicting the paths of the player and the target
+pub mod predict;
+/// The minimap
+pub mod radar;
+/// The view into the world
+pub mod camera;
+/// Particle effects
+pub mod particle;
+/// The debug and performance overlay
+pub mod debug;
+/// Gauges for the player's ship
+pub mod hud;
+/// The layers behind the world
+pub mod background;
+/// Stars and nebulae generated from the seed
+pub mod starfield;
+/// Picking what the mouse spawns
+pub mod editor;
 
 use self::world::*;
+use self::score::*;
+use self::mission::*;
+use self::gen::Field;
+use self::predict::Prediction;
+use self::radar::Radar;
+use self::camera::Camera;
+use self::particle::{Particles, Emitter, EXHAUST};
+use self::debug::DebugOverlay;
+use self::hud::Hud;
+use self::background::{Background, load_backgrounds, DEFAULT_BACKGROUND};
+use self::starfield::Starfield;
+use self::editor::SpawnEditor;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use std::rc::Rc;
 
-/// The state of the game
-pub struct State {
+/// The gameplay scene
+pub struct Game {
     input: InputState,
-    assets: Assets,
+    assets: Rc<Assets>,
     width: u32,
     height: u32,
     mouse: Point2,
     lines: bool,
     ast_spawn_coords: Option<Point2>,
     fuel_spawn_coords: Option<Point2>,
-    offset: Vector2,
+    editor: SpawnEditor,
+    camera: Camera,
+    /// The backgrounds scenarios can choose from by name
+    backgrounds: HashMap<String, Background>,
+    starfield: Starfield,
     world: World,
-    fuel_text: PosText,
-    fuel_usg_text: PosText,
-    health_text: PosText,
+    /// The HUD
+    ui: Ui,
+    hud: Hud,
+    high_scores: HighScores,
+    /// The name being typed in after dying, if any
+    name_entry: Option<String>,
+    score_submitted: bool,
+    missions: Vec<Mission>,
+    next_mission: usize,
+    message: String,
+    /// Seconds left to show the message
+    message_time: f32,
+    /// The predicted paths if the overlay is turned on
+    prediction: Option<Prediction>,
+    radar: Radar,
+    particles: Particles,
+    particle_batch: SpriteBatch,
+    /// Exhaust coming out of the player's engine
+    exhaust: Emitter,
+    /// How many objects were drawn and culled last frame
+    draw_stats: DrawStats,
+    debug: DebugOverlay,
 }
 
-const DESIRED_FPS: u32 = 60;
+pub(crate) const DESIRED_FPS: u32 = 60;
 
 pub(crate) const DELTA: f32 = 1. / DESIRED_FPS as f32;
 pub(crate) const DDELTA: f64 = 1. / DESIRED_FPS as f64;
 
-impl State {
-    /// Make a new state object
-    pub fn new(ctx: &mut Context) -> GameResult<Self> {
-        // Background colour is black
-        graphics::set_background_color(ctx, (0, 0, 0, 255).into());
-        // Initialise assets
-        let assets = Assets::new(ctx)?;
+const HIGH_SCORES_FILE: &str = "highscores.sav";
+/// File the game is saved in and loaded from
+pub(crate) const SAVE_FILE: &str = "save.sav";
+/// The longest name that can be entered in the high-score table
+const MAX_NAME_LEN: usize = 12;
+/// How many seconds mission messages are shown for
+const MESSAGE_TIME: f32 = 3.;
+/// How many times the resolution of the screen the view is captured in
+const CAPTURE_SCALE: f32 = 4.;
+/// Widest and tallest image a capture can make
+const MAX_CAPTURE_SIZE: u32 = 16384;
 
+impl Game {
+    /// Start a new game with the asteroid field generated from `seed`
+    pub fn new(ctx: &mut Context, assets: Rc<Assets>, seed: u64) -> GameResult<Self> {
         // Get the window's dimensions
-        let width = ctx.conf.window_mode.width;
-        let height = ctx.conf.window_mode.height;
+        let (width, height) = graphics::get_size(ctx);
 
-        // Initialise the text objects
-        let fuel_text = assets.text(ctx, Point2::new(2.0, 0.0), "Fuel: 99999.99 L")?;
-        let fuel_usg_text = assets.text(ctx, Point2::new(2.0, 16.0), "Throttle: 33.3 L/s")?;
-        let health_text = assets.text_ra(ctx, width as f32 - 5.0, 18.0, "Health: 999")?;
+        let ui = Ui::new(assets.clone(), width, height);
+        let radar = Radar::new(ctx, &assets, width, height)?;
+        let particle_batch = particle::particle_batch(ctx)?;
 
-        Ok(State {
+        // Initalise the player in the middle of the screen
+        let start = Point2::new(width as f32 / 2., height as f32 / 2.);
+        let player = make_player(start);
+        let hud = Hud::new(&player);
+
+        // Start with an empty table if there is no high-score file yet
+        let high_scores = save::load_scores(HIGH_SCORES_FILE).unwrap_or_default();
+        let missions = load_missions(ctx)?;
+        let backgrounds = load_backgrounds(ctx, &assets)?;
+        let starfield = Starfield::new(ctx)?;
+
+        Ok(Game {
             input: Default::default(),
             assets,
             width,
             height,
             ast_spawn_coords: None,
             fuel_spawn_coords: None,
+            editor: SpawnEditor::default(),
             lines: false,
-            fuel_text,
-            fuel_usg_text,
-            health_text,
+            ui,
+            hud,
+            high_scores,
+            name_entry: None,
+            score_submitted: false,
+            missions,
+            next_mission: 0,
+            message: String::new(),
+            message_time: 0.,
+            prediction: None,
+            radar,
+            particles: Particles::new(seed),
+            particle_batch,
+            exhaust: Emitter::new(EXHAUST, 60., 22.),
+            draw_stats: DrawStats::default(),
+            debug: DebugOverlay::default(),
             mouse: Point2::new(0., 0.),
-            offset: Vector2::new(0., 0.),
+            camera: Camera::new(start, width, height),
+            backgrounds,
+            starfield,
             world: World {
                 bullets: Objects::new(Vec::new(), Sprite::Bullet),
                 // The world starts of with one asteroid at (150, 150)
                 asteroids: Objects::new(vec![make_asteroid(Point2::new(150., 150.))], Sprite::Asteroid),
-                // Initalise the player in the middle of the screen
-                player: make_player(Point2::new(width as f32 / 2., height as f32 / 2.)),
-                fuels: Objects::new(Vec::new(), Sprite::Fuel),
+                player,
+                pickups: Objects::new(Vec::new(), Sprite::Fuel),
+                loadout: Loadout::default(),
+                score: Score::default(),
+                mission: None,
+                field: Field::new(seed),
+                target: None,
+                effects: Vec::new(),
+                collision_pairs: 0,
             }
         })
     }
-    /// Update the text objects
-    fn update_ui(&mut self, ctx: &mut Context) {
-        // Using formatting to round of the numbers to 2 decimals (the `.2` part)
-        let fuel_str = format!("Fuel: {:8.2} L", self.world.player.thruster.fuel);
-        let fuel_usg_str = format!("Throttle: {:2.1} L/s", self.world.player.thruster.throttle_usage);
-        let health_str = format!("Health: {:3.0}", self.world.player.health);
-
-        self.fuel_text.update_text(&self.assets, ctx, &fuel_str).unwrap();
-        self.fuel_usg_text.update_text(&self.assets, ctx, &fuel_usg_str).unwrap();
-        self.health_text.update_text(&self.assets, ctx, &health_str).unwrap();
-    }
-    /// Sets the offset so that the given point will be centered on the screen
-    fn focus_on(&mut self, p: Point2) {
-        self.offset = -p.coords + 0.5 * Vector2::new(self.width as f32, self.height as f32);
-    }
-    /// Draws repeating parallax background
-    pub fn draw_bg(&mut self, ctx: &mut Context, scale: f32, s: Sprite) -> GameResult<()> {
-        let p = scale * self.offset;
-        let mut x = p.x;
-        let mut y = p.y;
+    /// Makes the HUD
+    fn update_ui(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let mode = self.mode();
+        let ui = &mut self.ui;
+        let world = &self.world;
+        ui.begin();
 
-        let (w, h) = (s.width(), s.height());
-
-        while x < 0. {
-            x += w;
+        ui.begin_panel(Anchor::TopLeft, 2.);
+        self.hud.gauges(ui, ctx, &world.player)?;
+        if let Some(ref mission) = world.mission {
+            let text = mission.hud_text(&world.player, &world.score);
+            match mission.status() {
+                MissionStatus::Active => ui.label(ctx, &text)?,
+                MissionStatus::Complete => ui.label_colored(ctx, &text, GREEN)?,
+                MissionStatus::Failed => ui.label_colored(ctx, &text, RED)?,
+            }
+        }
+        if self.message_time > 0. {
+            ui.label(ctx, &self.message)?;
         }
-        x %= w;
-        while y < 0. {
-            y += h;
+        if let Some(ref target) = world.target {
+            let rel = target.relative_to(&world.player);
+            ui.label(ctx, &format!("Target: {} {:.0} px away, closing at {:.1} px/s",
+                target.kind.name(), rel.distance, rel.closing_speed))?;
+            ui.label(ctx, &match rel.tca {
+                Some(tca) => format!("Rel. vel: ({:.1}, {:.1}) Closest: {:.0} px in {:.1} s",
+                    rel.rel_vel.x, rel.rel_vel.y, rel.ca_distance, tca),
+                None => format!("Rel. vel: ({:.1}, {:.1}) Moving apart",
+                    rel.rel_vel.x, rel.rel_vel.y),
+            })?;
         }
-        y %= h;
 
-        let img = self.assets.get_img(s);
-        graphics::draw(ctx, img, Point2::new(x, y), 0.)?;
-        graphics::draw(ctx, img, Point2::new(x-w, y), 0.)?;
-        graphics::draw(ctx, img, Point2::new(x, y-h), 0.)?;
-        graphics::draw(ctx, img, Point2::new(x-w, y-h), 0.)?;
+        ui.begin_panel(Anchor::TopRight, 5.);
+        let autopilot_name = world.player.autopilot.map(|a| a.name()).unwrap_or("Off");
+        ui.label(ctx, &format!("Score: {:6}", world.score.total()))?;
+        ui.label(ctx, &format!("{:>6}: {:4}", world.loadout.weapon().name(), world.loadout.ammo))?;
+        ui.label(ctx, &format!("Autopilot: {:>13}", autopilot_name))?;
+        self.debug.panel(ui, ctx, world, self.draw_stats, self.particles.count())?;
 
+        if world.player.is_dead() {
+            ui.begin_panel(Anchor::Center, 0.);
+            ui.label(ctx, &format!("GAME OVER - Score: {}", world.score.total()))?;
+            if let Some(ref name) = self.name_entry {
+                ui.label(ctx, &format!("New high score! Name: {}_", name))?;
+            }
+            ui.space(16.);
+            ui.label(ctx, &format!("High scores ({})", mode.name()))?;
+            for (i, entry) in self.high_scores.for_mode(mode).enumerate() {
+                ui.label(ctx, &format!("{:2}. {:12} {:8}", i + 1, entry.name, entry.score))?;
+            }
+        }
+        self.editor.panel(ui, ctx)?;
+        ui.end();
         Ok(())
     }
-}
-
-
-impl EventHandler for State {
-    // Handle the game logic
-    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
-        // Run this for every 1/60 of a second has passed since last update
-        // Can in theory become slow
-        while timer::check_update_time(ctx, DESIRED_FPS) {
-            self.world.physics_update(&self.input);
+    /// Draws an arrow at the edge of the screen pointing to a point outside of it
+    fn draw_edge_arrow(&self, ctx: &mut Context, p: Point2) -> GameResult<()> {
+        const MARGIN: f32 = 20.;
+        let (w, h) = (self.width as f32, self.height as f32);
+        let screen_pos = self.camera.to_screen(p);
+        if screen_pos.x >= 0. && screen_pos.x <= w && screen_pos.y >= 0. && screen_pos.y <= h {
+            return Ok(())
         }
+        let center = Point2::new(w / 2., h / 2.);
+        let dir = na::normalize(&(screen_pos - center));
+        let perp = Vector2::new(-dir.y, dir.x);
 
-        self.world.player.obj.rot %= 2.*::std::f32::consts::PI;
+        // Move from the centre along the direction until hitting the margin
+        let t = ((w / 2. - MARGIN) / dir.x.abs()).min((h / 2. - MARGIN) / dir.y.abs());
+        let tip = center + t * dir;
+        let points = [tip, tip - 15. * dir + 8. * perp, tip - 15. * dir - 8. * perp];
 
-        // Update the UI
-        self.update_ui(ctx);
-        // Center the camera on the player
-        let p = self.world.player.pos;
-        self.focus_on(p);
+        graphics::polygon(ctx, graphics::DrawMode::Fill, &points)
+    }
+    /// The mode the current game counts as in the high-score table
+    fn mode(&self) -> Mode {
+        if self.world.mission.is_some() {
+            Mode::Mission
+        } else {
+            Mode::Sandbox
+        }
+    }
+    /// Starts the next mission from the missions file
+    fn start_next_mission(&mut self) {
+        if self.missions.is_empty() {
+            return
+        }
+        let mission = self.missions[self.next_mission].clone();
+        self.next_mission = (self.next_mission + 1) % self.missions.len();
 
+        let msg = format!("Mission started: {}", mission.name);
+        self.world.mission = Some(MissionProgress::new(mission));
+        self.show_message(&msg);
+    }
+    /// Shows a message under the HUD for a few seconds
+    fn show_message(&mut self, msg: &str) {
+        self.message = msg.to_owned();
+        self.message_time = MESSAGE_TIME;
+    }
+    /// Shows the error of something that failed under the HUD
+    fn report(&mut self, what: &str, result: GameResult<()>) {
+        if let Err(e) = result {
+            self.show_message(&format!("{}: {}", what, e));
+        }
+    }
+    /// Shows messages for the events of the current mission
+    fn handle_mission_events(&mut self) {
+        for event in self.world.mission_events() {
+            let msg = match event {
+                MissionEvent::ObjectiveComplete(desc) => format!("Objective complete: {}", desc),
+                MissionEvent::MissionComplete(name) => format!("Mission complete: {}", name),
+                MissionEvent::MissionFailed(reason) => format!("Mission failed: {}", reason),
+            };
+            self.show_message(&msg);
+        }
+    }
+    /// Starts the game over screen once the player has died
+    fn check_game_over(&mut self) {
+        if self.score_submitted || self.name_entry.is_some() || !self.world.player.is_dead() {
+            return
+        }
+        let score = self.world.score.total();
+        if self.high_scores.qualifies(self.mode(), score) {
+            // Forget held keys since they'll be used for typing now
+            self.input = Default::default();
+            self.name_entry = Some(String::new());
+        } else {
+            self.score_submitted = true;
+        }
+    }
+    /// Puts the entered name in the high-score table and saves it
+    fn submit_score(&mut self) {
+        if let Some(name) = self.name_entry.take() {
+            let name = if name.trim().is_empty() { "???".to_owned() } else { name };
+            let mode = self.mode();
+            self.high_scores.insert(name, mode, self.world.score.total());
+            self.score_submitted = true;
+            if let Err(e) = save::save_scores(HIGH_SCORES_FILE, &self.high_scores) {
+                println!("Couldn't save high scores: {}", e);
+            }
+        }
+    }
+    /// Loads a saved world
+    pub fn load(&mut self, path: &str) -> GameResult<()> {
+        save::load(path, &mut self.world)?;
+        self.camera.snap_to(self.world.player.pos);
+        // The loaded game hasn't had its score submitted yet
+        self.score_submitted = false;
         Ok(())
     }
+    /// Makes the parts of the game follow the settings
+    fn apply_settings(&mut self, settings: &Settings) {
+        self.radar.visible = settings.show_radar;
+        self.camera.rotate_with_ship = settings.rotate_with_ship;
+        self.debug.visible = settings.debug_overlay;
+        self.hud.compact = settings.compact_hud;
+    }
+    /// Makes particles for what happened this tick and moves the old ones
+    fn update_particles(&mut self) {
+        for effect in self.world.take_effects() {
+            self.particles.effect(effect);
+        }
+        self.exhaust.exhaust(&mut self.particles, &self.world.player);
+        self.particles.update();
+    }
+    /// Draws the background of the current mission, or the default one
+    ///
+    /// The stars are generated from the seed of the world, so a loaded game has the same sky
+    fn draw_background(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let name = self.world.mission.as_ref().and_then(|m| m.background()).unwrap_or(DEFAULT_BACKGROUND);
+        let seed = self.world.field.seed();
+        let name = if self.backgrounds.contains_key(name) { name } else { DEFAULT_BACKGROUND };
+        match self.backgrounds.get_mut(name) {
+            Some(bg) => bg.draw(ctx, &self.assets, &mut self.starfield, &self.camera, seed),
+            None => Ok(()),
+        }
+    }
+    /// Draws the background and everything in the world as seen through the camera
+    fn draw_world(&mut self, ctx: &mut Context) -> GameResult<()> {
+        self.draw_background(ctx)?;
 
-    // Draws everything
-    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
-        // Clear the screen first
-        graphics::clear(ctx);
-        self.draw_bg(ctx, 0.1, Sprite::StarsBg)?;
-
-        // Offset the current drawing with a translation from the `offset`
-        graphics::push_transform(ctx, Some(Matrix4::new_translation(&self.offset.fixed_resize(0.))));
+        // Transform the current drawing from the world to the screen with the camera
+        graphics::push_transform(ctx, Some(self.camera.transform()));
         graphics::apply_transformations(ctx)?;
 
+        // Particles go under everything else so the exhaust comes out from behind the ship
+        self.particles.draw(ctx, &mut self.particle_batch)?;
+
         // Draw player and asteroids
         let s = self.world.player.thruster.sprite();
-        self.world.player.draw(ctx, self.assets.get_img(s))?;
-        self.world.asteroids.draw(ctx, &self.assets)?;
-        self.world.fuels.draw(ctx, &self.assets)?;
-        self.world.bullets.draw(ctx, &self.assets)?;
+        self.world.player.draw(ctx, &self.assets, s)?;
+        let view = self.camera.view_rect();
+        let mut draw_stats = DrawStats::default();
+        draw_stats += self.world.asteroids.draw(ctx, &self.assets, &view)?;
+        draw_stats += self.world.pickups.draw(ctx, &self.assets, &view)?;
+        draw_stats += self.world.bullets.draw(ctx, &self.assets, &view)?;
+        self.draw_stats = draw_stats;
+
+        // Mark where the current objective wants the ship to go
+        if let Some(ref mission) = self.world.mission {
+            if let Some(p) = mission.target_pos(&self.world.asteroids) {
+                let radius = mission.objective().map(|obj| obj.radius).unwrap_or(50.);
+                graphics::set_color(ctx, BLUE)?;
+                graphics::circle(ctx, graphics::DrawMode::Line(2.), p, radius, 1.)?;
+            }
+        }
+
+        // Mark the target
+        if let Some(ref target) = self.world.target {
+            graphics::set_color(ctx, YELLOW)?;
+            graphics::circle(ctx, graphics::DrawMode::Line(2.), target.pos, target.rad + 8., 1.)?;
+        }
+
+        if let Some(ref prediction) = self.prediction {
+            prediction.draw(ctx)?;
+        }
 
         // If lines is turned on, draw lines for the velocity and acceleration vectors from the objects
         if self.lines {
             self.world.asteroids.draw_lines(ctx)?;
-            self.world.fuels.draw_lines(ctx)?;
+            self.world.pickups.draw_lines(ctx)?;
             self.world.bullets.draw_lines(ctx)?;
             self.world.player.draw_lines(ctx)?;
         }
 
-        // Pop the offset tranformation to draw the UI on the screen
+        // Pop the camera tranformation to go back to drawing on the screen
         graphics::pop_transform(ctx);
         graphics::apply_transformations(ctx)?;
-
+        Ok(())
+    }
+    /// Draws what goes on top of the world on the screen
+    fn draw_hud(&mut self, ctx: &mut Context) -> GameResult<()> {
         // Check if an asteroid is being spawned
         if let Some(proto_pos) = self.ast_spawn_coords {
             // Draw the asteroid transparently so you can see you're making an asteroid
+            let (img, info) = (self.assets.get_img(Sprite::Asteroid), self.assets.info(Sprite::Asteroid));
+            let ast = self.editor.asteroid(proto_pos);
+            let scale = self.camera.zoom * ast.rad / info.draw_radius();
             let params = graphics::DrawParam {
+                src: info.src(img, 0),
                 dest: proto_pos,
-                offset: Point2::new(0.5, 0.5),
-                color: Some(TRANS),
+                offset: info.offset(),
+                scale: Point2::new(scale, scale),
+                color: Some(Color { a: TRANS.a, .. ast.material.tint() }),
                 .. Default::default()
             };
-            graphics::draw_ex(ctx, self.assets.get_img(Sprite::Asteroid), params)?;
+            graphics::draw_ex(ctx, img, params)?;
             if self.lines {
                 graphics::set_color(ctx, GREEN)?;
                 graphics::line(ctx, &[proto_pos, proto_pos - (self.mouse-proto_pos)], 2.)?;
@@ -176,56 +427,212 @@ impl EventHandler for State {
         }
         if let Some(proto_pos) = self.fuel_spawn_coords {
             // Draw the asteroid transparently so you can see you're making an asteroid
+            let (img, info) = (self.assets.get_img(Sprite::Fuel), self.assets.info(Sprite::Fuel));
             let params = graphics::DrawParam {
+                src: info.src(img, 0),
                 dest: proto_pos,
-                offset: Point2::new(0.5, 0.5),
+                offset: info.offset(),
+                scale: Point2::new(self.camera.zoom, self.camera.zoom),
                 color: Some(TRANS),
                 .. Default::default()
             };
-            graphics::draw_ex(ctx, self.assets.get_img(Sprite::Fuel), params)?;
+            graphics::draw_ex(ctx, img, params)?;
             if self.lines {
                 graphics::set_color(ctx, GREEN)?;
                 graphics::line(ctx, &[proto_pos, proto_pos - (self.mouse-proto_pos)], 2.)?;
             }
         }
 
-        // Draw the text in white
-        graphics::set_color(ctx, graphics::WHITE)?;
-        self.fuel_text.draw_text(ctx)?;
-        self.fuel_usg_text.draw_text(ctx)?;
-        self.health_text.draw_text(ctx)?;
+        if let Some(ref target) = self.world.target {
+            graphics::set_color(ctx, YELLOW)?;
+            self.draw_edge_arrow(ctx, target.pos)?;
+        }
+        self.ui.draw(ctx)?;
+        self.radar.draw(ctx, &self.world)
+    }
+    /// Draws a region of the world onto a canvas one screen at a time and saves it as a single PNG
+    ///
+    /// `scale` is how many pixels in the image a pixel in the world is.
+    /// The HUD is stretched over the whole image if `hud` is set.
+    /// Only the part of the asteroid field that is loaded around the camera is in the image
+    pub fn capture_region(&mut self, ctx: &mut Context, region: Rect, scale: f32, hud: bool) -> GameResult<PathBuf> {
+        let (width, height) = ((region.w * scale).round() as u32, (region.h * scale).round() as u32);
+        if width == 0 || height == 0 || width > MAX_CAPTURE_SIZE || height > MAX_CAPTURE_SIZE {
+            return Err(GameError::RenderError(format!("Can't capture an image of {}x{}", width, height)))
+        }
+        // Each part of the image is drawn like a screen, one pixel per unit of the screen coordinates
+        let (tile_w, tile_h) = (self.width, self.height);
+        if tile_w == 0 || tile_h == 0 {
+            return Err(GameError::RenderError("Can't capture with a screen of no size".to_owned()))
+        }
+        let hud_scale = (width as f32 / tile_w as f32).min(height as f32 / tile_h as f32);
+        let mut pixels = vec![0u8; width as usize * height as usize * 4];
+        // Drawn to instead of the window, so it doesn't matter if the window can be seen
+        let canvas = graphics::Canvas::new(ctx, tile_w, tile_h, conf::NumSamples::One)?;
+
+        // A camera looking at the middle of the region, of which each tile is a section
+        let mut whole = Camera::new(Point2::new(region.x + region.w / 2., region.y + region.h / 2.), tile_w, tile_h);
+        whole.zoom = scale;
+
+        for y0 in (0..height).step_by(tile_h as usize) {
+            for x0 in (0..width).step_by(tile_w as usize) {
+                // Move the centre of the tile to the centre of this part of the image
+                let offset = Vector2::new(
+                    x0 as f32 + (tile_w as f32 - width as f32) / 2.,
+                    y0 as f32 + (tile_h as f32 - height as f32) / 2.,
+                );
+                graphics::set_canvas(ctx, Some(&canvas));
+                let view = ::std::mem::replace(&mut self.camera, whole.section(offset));
+                graphics::clear(ctx);
+                let mut result = self.draw_world(ctx);
+                self.camera = view;
+
+                if hud && result.is_ok() {
+                    let transform = Matrix4::new_translation(&na::Vector3::new(-(x0 as f32), -(y0 as f32), 0.))
+                        * Matrix4::new_scaling(hud_scale);
+                    graphics::push_transform(ctx, Some(transform));
+                    result = graphics::apply_transformations(ctx).and_then(|()| self.draw_hud(ctx));
+                    graphics::pop_transform(ctx);
+                    graphics::apply_transformations(ctx)?;
+                }
+                if let Err(e) = result {
+                    graphics::set_canvas(ctx, None);
+                    return Err(e)
+                }
+
+                // Copy the part of the tile that is inside the image
+                let tile = screenshot::read_canvas(ctx, &canvas)?;
+                let row = (width - x0).min(tile_w) as usize * 4;
+                for y in 0..(height - y0).min(tile_h) as usize {
+                    let start = ((y0 as usize + y) * width as usize + x0 as usize) * 4;
+                    let tile_start = y * tile_w as usize * 4;
+                    pixels[start..start + row].copy_from_slice(&tile[tile_start..tile_start + row]);
+                }
+            }
+        }
+        screenshot::save_png("capture", &pixels, width, height)
+    }
+    /// Captures what the camera sees in a higher resolution without the HUD
+    fn capture_view(&mut self, ctx: &mut Context) {
+        let region = self.camera.view_rect();
+        let scale = self.camera.zoom * CAPTURE_SCALE;
+        match self.capture_region(ctx, region, scale, false) {
+            Ok(path) => self.show_message(&format!("Saved {}", path.display())),
+            Err(e) => self.show_message(&format!("Couldn't capture the view: {}", e)),
+        }
+    }
+}
+
+
+impl Scene for Game {
+    // Handle the game logic
+    fn update(&mut self, ctx: &mut Context, shared: &mut Shared) -> GameResult<Transition> {
+        self.apply_settings(&shared.settings);
+
+        // Run this for every 1/60 of a second has passed since last update
+        // Can in theory become slow
+        // The ship drifts on its own while the camera is moved by hand
+        let idle = InputState::default();
+        let mut ticks = 0;
+        let mut physics_time = Duration::new(0, 0);
+        let mut collision_pairs = 0;
+        while timer::check_update_time(ctx, DESIRED_FPS) {
+            ticks += 1;
+            let input = if self.camera.free { &idle } else { &self.input };
+            // Only the world's physics are timed, not the camera, HUD or particles
+            let physics_start = Instant::now();
+            self.world.physics_update(input);
+            physics_time += physics_start.elapsed();
+            collision_pairs += self.world.collision_pairs;
+            self.camera.pan(Vector2::new(self.input.rot(), -self.input.ver()));
+            self.camera.follow(&self.world.player);
+            self.hud.tick(&self.world.player);
+            self.starfield.tick();
+            self.update_particles();
+            self.message_time -= DELTA;
+            if let Some(ref mut prediction) = self.prediction {
+                prediction.tick();
+            }
+        }
+        self.debug.record_update(ctx, ticks, physics_time, collision_pairs);
+        self.handle_mission_events();
+
+        // Predict again when the controls change so it follows them, or when it gets old
+        let input = if self.camera.free { &idle } else { &self.input };
+        let stale = self.prediction.as_ref().map_or(false, |p| p.is_stale(&self.world, input));
+        if stale {
+            self.prediction = Some(Prediction::new(&self.world, input));
+        }
+
+        self.world.player.obj.rot %= 2.*::std::f32::consts::PI;
 
-        // Flip the buffers to see what we just drew
-        graphics::present(ctx);
+        // Update the UI
+        self.update_ui(ctx)?;
+        self.check_game_over();
+        // Generate the asteroid field around the centre of the screen
+        let center = self.camera.pos;
+        self.world.update_chunks(center);
 
-        // Give the computer some time to do other things
-        timer::yield_now();
+        Ok(Transition::None)
+    }
+
+    // Draws everything
+    fn draw(&mut self, ctx: &mut Context, _shared: &Shared) -> GameResult<()> {
+        let draw_start = Instant::now();
+        self.draw_world(ctx)?;
+        self.draw_hud(ctx)?;
+        self.debug.record_draw(draw_start.elapsed());
         Ok(())
     }
     /// Handle key down events
-    fn key_down_event(&mut self, ctx: &mut Context, keycode: Keycode, _: Mod, repeat: bool) {
-        // If this is a repeat event, we don't care
-        if repeat {
-            return
-        }
+    fn key_down(&mut self, ctx: &mut Context, shared: &mut Shared, keycode: Keycode) -> Transition {
         use Keycode::*;
-        // Update input axes and quit game on Escape
+        // Type in the name for the high-score table
+        if self.name_entry.is_some() {
+            match keycode {
+                Return => self.submit_score(),
+                Backspace => {
+                    if let Some(ref mut name) = self.name_entry {
+                        name.pop();
+                    }
+                }
+                _ => if let Some(c) = keycode_char(keycode) {
+                    if let Some(ref mut name) = self.name_entry {
+                        if name.len() < MAX_NAME_LEN {
+                            name.push(c);
+                        }
+                    }
+                }
+            }
+            return Transition::None
+        }
+        // Update input axes and pause the game on Escape
         match keycode {
             W | Up => self.input.ver += 1,
             S | Down => self.input.ver -= 1,
-            A | Left => self.input.hor -= 1,
-            D | Right => self.input.hor += 1,
+            A | Left => self.input.rot -= 1,
+            D | Right => self.input.rot += 1,
+            Q => self.input.strafe -= 1,
+            E => self.input.strafe += 1,
             LShift => self.input.throttle += 1,
             LCtrl => self.input.throttle -= 1,
-            Escape => ctx.quit().unwrap(),
-            _ => return,
+            Escape => {
+                // Keys released while paused won't be seen, so forget them now
+                self.input = Default::default();
+                return match PauseMenu::new(ctx, shared) {
+                    Ok(pause) => Transition::Push(Box::new(pause)),
+                    Err(_) => Transition::None,
+                };
+            }
+            _ => (),
         }
+        Transition::None
     }
     /// Handle key release events
-    fn key_up_event(&mut self, _ctx: &mut Context, keycode: Keycode, _: Mod, repeat: bool) {
-        // Still don't care about repeats
-        if repeat {
-            return
+    fn key_up(&mut self, ctx: &mut Context, shared: &mut Shared, keycode: Keycode) -> Transition {
+        // Don't care about keys used for typing in a name
+        if self.name_entry.is_some() {
+            return Transition::None
         }
         use Keycode::*;
         // Update input axes in the opposite direction
@@ -233,48 +640,113 @@ impl EventHandler for State {
         // Clear all asteroids on R
         // Save the current `world` on Z
         // Load the last save on X
+        // Start the next mission on M
+        // Switch weapon on Tab
+        // Cycle targets on T
+        // Choose autopilot mode with the numbers (0 turns it off)
+        // Toggle the trajectory prediction on P
+        // Toggle the radar on N and zoom it with - and +
+        // Toggle the spectator camera on C and turning the view with the ship on V
+        // Toggle the spawn editor on F2 and the debug overlay on F3
+        // Toggle the compact HUD on H
+        // Capture the view in a high resolution on F11
         match keycode {
             W | Up => self.input.ver -= 1,
             S | Down => self.input.ver += 1,
-            A | Left => self.input.hor += 1,
-            D | Right => self.input.hor -= 1,
+            A | Left => self.input.rot += 1,
+            D | Right => self.input.rot -= 1,
+            Q => self.input.strafe += 1,
+            E => self.input.strafe -= 1,
             LShift => self.input.throttle -= 1,
             LCtrl => self.input.throttle += 1,
             L => self.lines.toggle(),
             R => self.world.asteroids.clear(),
             I => self.world.player.thruster.throttle_usage = 0.,
-            Z => save::save("save.sav", &self.world).unwrap(),
-            X => save::load("save.sav", &mut self.world).unwrap(),
-            Space => {
-                let d = angle_to_vec(self.world.player.rot);
-
-                let mut bullet = make_bullet(self.world.player.pos + 34. * d);
-                bullet.vel = self.world.player.vel + 200. * d;
-                bullet.rot = self.world.player.rot;
-                self.world.bullets.push(bullet);
+            M => self.start_next_mission(),
+            Z => {
+                let result = save::save(SAVE_FILE, &self.world);
+                self.report("Couldn't save the game", result);
+            }
+            X => {
+                let result = self.load(SAVE_FILE);
+                self.report("Couldn't load the game", result);
+            }
+            Tab => self.world.loadout.cycle(),
+            T => {
+                let p = self.world.player.pos;
+                self.world.target = target::cycle(self.world.target.as_ref(), p, &self.world.asteroids, &self.world.pickups);
             }
-            _ => return,
+            Num0 => self.world.player.autopilot = None,
+            Num1 => self.world.player.autopilot = Some(Autopilot::KillVelocity),
+            Num2 => {
+                let heading = self.world.player.rot;
+                self.world.player.autopilot = Some(Autopilot::HoldHeading(heading));
+            }
+            Num3 => self.world.player.autopilot = Some(Autopilot::MatchVelocity),
+            Num4 => {
+                // Approach the point under the mouse
+                let p = self.camera.to_world(self.mouse);
+                self.world.player.autopilot = Some(Autopilot::Approach(p.x, p.y));
+            }
+            Num5 => self.world.player.autopilot = Some(Autopilot::Prograde),
+            Num6 => self.world.player.autopilot = Some(Autopilot::Retrograde),
+            Space => self.world.fire(),
+            P => {
+                self.prediction = match self.prediction {
+                    Some(_) => None,
+                    None => Some(Prediction::new(&self.world, &self.input)),
+                };
+            }
+            N => shared.settings.show_radar.toggle(),
+            C => self.camera.free.toggle(),
+            V => shared.settings.rotate_with_ship.toggle(),
+            F2 => self.editor.visible.toggle(),
+            F3 => shared.settings.debug_overlay.toggle(),
+            H => shared.settings.compact_hud.toggle(),
+            F11 => self.capture_view(ctx),
+            Minus | KpMinus => {
+                let result = self.radar.zoom_out(ctx, &self.assets);
+                self.report("Couldn't zoom the radar", result);
+            }
+            Equals | KpPlus => {
+                let result = self.radar.zoom_in(ctx, &self.assets);
+                self.report("Couldn't zoom the radar", result);
+            }
+            _ => (),
         }
+        Transition::None
     }
     /// Handle mouse down event
-    fn mouse_button_down_event(&mut self, _ctx: &mut Context, btn: MouseButton, x: i32, y: i32) {
+    fn mouse_down(&mut self, _ctx: &mut Context, _shared: &mut Shared, btn: MouseButton, p: Point2) -> Transition {
+        // Clicks on the spawn editor don't spawn anything
+        if self.ui.is_over_widget(p) {
+            self.ui.mouse_down(btn, p);
+            return Transition::None
+        }
         // Set the spawn_coords so we can spawn an asteroid when the button is released
         if let MouseButton::Left = btn {
-            self.ast_spawn_coords = Some(Point2::new(x as f32, y as f32));
+            self.ast_spawn_coords = Some(p);
         }
         if let MouseButton::Right = btn {
-            self.fuel_spawn_coords = Some(Point2::new(x as f32, y as f32));
+            self.fuel_spawn_coords = Some(p);
+        }
+        // Select the object under the mouse as the target
+        if let MouseButton::Middle = btn {
+            let p = self.camera.to_world(p);
+            self.world.target = target::pick(p, &self.world.asteroids, &self.world.pickups);
         }
+        Transition::None
     }
     /// Handle mouse release events
-    fn mouse_button_up_event(&mut self, _ctx: &mut Context, btn: MouseButton, x: i32, y: i32) {
+    fn mouse_up(&mut self, _ctx: &mut Context, _shared: &mut Shared, btn: MouseButton, up: Point2) -> Transition {
+        self.ui.mouse_up(btn, up);
         if let MouseButton::Left = btn {
             // Get the spawn_coords and replace them with `None`
             if let Some(p) = ::std::mem::replace(&mut self.ast_spawn_coords, None) {
                 // Make a new asteroid object wherever the mouse pointed when the button was pressed down
-                let mut ast = make_asteroid(p - self.offset);
+                let mut ast = self.editor.asteroid(self.camera.to_world(p));
                 // Set the velocity so it moves towards where the mouse is now
-                ast.vel = ast.pos - Point2::new(x as f32, y as f32) + self.offset;
+                ast.vel = ast.pos - self.camera.to_world(up);
                 ast.vel += self.world.player.vel;
                 // Push it to the asteroids vector ("dynamic array" not a maths vector)
                 self.world.asteroids.push(ast);
@@ -284,23 +756,48 @@ impl EventHandler for State {
             // Get the spawn_coords and replace them with `None`
             if let Some(p) = ::std::mem::replace(&mut self.fuel_spawn_coords, None) {
                 // Make a new object wherever the mouse pointed when the button was pressed down
-                let mut fuel = make_fuel(p - self.offset);
+                let mut fuel = self.editor.fuel(self.camera.to_world(p));
                 // Set the velocity so it moves towards where the mouse is now
-                fuel.vel = fuel.pos - Point2::new(x as f32, y as f32) + self.offset;
-                fuel.vel += self.world.player.vel;
-                self.world.fuels.push(fuel);
+                fuel.obj.vel = fuel.obj.pos - self.camera.to_world(up);
+                fuel.obj.vel += self.world.player.vel;
+                self.world.pickups.push(fuel);
             }
         }
+        Transition::None
+    }
+    /// Zoom with the mouse wheel
+    fn mouse_wheel(&mut self, _: &mut Context, _: &mut Shared, y: i32) {
+        self.camera.zoom_by(1.1f32.powi(y));
     }
     /// Handles mouse movement events
-    fn mouse_motion_event(&mut self, _: &mut Context, _: MouseState, x: i32, y: i32, _: i32, _: i32) {
-        self.mouse = Point2::new(x as f32, y as f32);
+    fn mouse_motion(&mut self, _: &mut Context, _: &mut Shared, p: Point2) {
+        self.mouse = p;
+        self.ui.mouse_motion(p);
     }
-    fn quit_event(&mut self, _ctx: &mut Context) -> bool {
-        println!("Closing, auto-saving game");
+    fn on_quit(&mut self, _ctx: &mut Context) {
+        println!("Leaving the game, auto-saving");
         // Save the world state to a file
-        save::save("autosave.sav", &self.world).unwrap();
-
-        false
+        if let Err(e) = save::save("autosave.sav", &self.world) {
+            println!("Couldn't auto-save: {}", e);
+        }
+    }
+    /// Keeps the view centred and the HUD in the corners of the new screen
+    fn resize(&mut self, _ctx: &mut Context, width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+        self.camera.resize(width, height);
+        self.ui.resize(width, height);
+        self.radar.resize(width, height);
+    }
+    /// Switches to the new assets and loads the missions and backgrounds again
+    ///
+    /// The world is kept as it is
+    fn reload(&mut self, ctx: &mut Context, assets: &Rc<Assets>) -> GameResult<()> {
+        self.assets = assets.clone();
+        self.ui.set_assets(assets.clone());
+        self.backgrounds = load_backgrounds(ctx, assets)?;
+        self.missions = load_missions(ctx)?;
+        self.next_mission %= self.missions.len().max(1);
+        Ok(())
     }
 }
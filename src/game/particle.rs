@@ -0,0 +1,317 @@
+use std::f32::consts::PI;
+
+use ::*;
+use ggez::graphics::SpriteBatch;
+use rng::Rng;
+use super::world::Effect;
+
+/// Most particles alive at the same time, new ones are dropped after this
+const MAX_PARTICLES: usize = 4096;
+/// Fuel usage in litres a second that gives the normal rate of exhaust
+const NORMAL_USAGE: f64 = 10.;
+/// Size of the square image particles are drawn with
+const PARTICLE_IMAGE_SIZE: u16 = 4;
+
+#[derive(Debug, Copy, Clone)]
+/// How a kind of particle looks and moves
+pub struct ParticleKind {
+    /// Shortest and longest time a particle lives
+    pub lifetime: (f32, f32),
+    /// Slowest and fastest speed a particle is shot out with
+    pub speed: (f32, f32),
+    /// How far off (in radians) from the direction particles are shot out
+    pub spread: f32,
+    /// Colour when it's made
+    pub start_color: Color,
+    /// Colour when it dies
+    pub end_color: Color,
+    /// Size when it's made
+    pub start_size: f32,
+    /// Size when it dies
+    pub end_size: f32,
+}
+
+/// Burning fuel from the main engine
+pub const EXHAUST: ParticleKind = ParticleKind {
+    lifetime: (0.2, 0.5),
+    speed: (150., 250.),
+    spread: 0.2,
+    start_color: Color{r:1.,g:0.9,b:0.4,a:1.},
+    end_color: Color{r:1.,g:0.2,b:0.,a:0.},
+    start_size: 4.,
+    end_size: 1.,
+};
+/// Sparks from bullets hitting something
+pub const SPARK: ParticleKind = ParticleKind {
+    lifetime: (0.1, 0.3),
+    speed: (80., 200.),
+    spread: PI,
+    start_color: Color{r:1.,g:1.,b:0.8,a:1.},
+    end_color: Color{r:1.,g:0.6,b:0.,a:0.},
+    start_size: 2.,
+    end_size: 1.,
+};
+/// Pieces of a broken asteroid, tinted with its colour when made
+pub const DEBRIS: ParticleKind = ParticleKind {
+    lifetime: (0.6, 1.5),
+    speed: (20., 90.),
+    spread: PI,
+    start_color: Color{r:0.8,g:0.8,b:0.8,a:1.},
+    end_color: Color{r:0.4,g:0.4,b:0.4,a:0.},
+    start_size: 5.,
+    end_size: 2.,
+};
+
+#[derive(Debug, Copy, Clone)]
+struct Particle {
+    pos: Point2,
+    vel: Vector2,
+    age: f32,
+    lifetime: f32,
+    start_color: Color,
+    end_color: Color,
+    start_size: f32,
+    end_size: f32,
+    alive: bool,
+}
+
+impl Particle {
+    /// How far through its life it is (`0...1`)
+    fn progress(&self) -> f32 {
+        (self.age / self.lifetime).min(1.)
+    }
+    fn color(&self) -> Color {
+        let t = self.progress();
+        let (a, b) = (self.start_color, self.end_color);
+        Color::new(lerp(a.r, b.r, t), lerp(a.g, b.g, t), lerp(a.b, b.b, t), lerp(a.a, b.a, t))
+    }
+    fn size(&self) -> f32 {
+        lerp(self.start_size, self.end_size, self.progress())
+    }
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+#[derive(Debug, Copy, Clone)]
+/// Makes particles continuously from an object
+pub struct Emitter {
+    /// What particles it makes
+    pub kind: ParticleKind,
+    /// Particles per second at full rate
+    pub rate: f32,
+    /// How far behind the centre of the object they come out
+    pub distance: f32,
+    /// Particles owed from earlier ticks
+    pending: f32,
+}
+
+impl Emitter {
+    /// Makes an emitter
+    pub fn new(kind: ParticleKind, rate: f32, distance: f32) -> Self {
+        Emitter {
+            kind,
+            rate,
+            distance,
+            pending: 0.,
+        }
+    }
+    /// Emits a tick worth of particles out of the back of an object
+    ///
+    /// `scale` multiplies the rate
+    pub fn emit(&mut self, particles: &mut Particles, obj: &Object, scale: f32) {
+        self.pending += self.rate * scale.max(0.) * DELTA;
+        let back = -angle_to_vec(obj.rot);
+        let pos = obj.pos + self.distance * back;
+        while self.pending >= 1. {
+            self.pending -= 1.;
+            particles.spawn(&self.kind, pos, back, obj.vel);
+        }
+    }
+    /// Emits a tick worth of exhaust from a ship if its engine is on, more the higher its throttle is
+    pub fn exhaust(&mut self, particles: &mut Particles, ship: &ThrustedObj) {
+        let thruster = &ship.thruster;
+        if thruster.power {
+            let scale = (thruster.throttle_usage / NORMAL_USAGE) as f32;
+            self.emit(particles, ship, scale);
+        }
+    }
+}
+
+/// A pool of particles
+pub struct Particles {
+    pool: Vec<Particle>,
+    /// Indices of dead particles that can be reused
+    free: Vec<usize>,
+    rng: Rng,
+}
+
+impl Particles {
+    /// Makes an empty pool
+    pub fn new(seed: u64) -> Self {
+        Particles {
+            pool: Vec::with_capacity(MAX_PARTICLES),
+            free: Vec::new(),
+            rng: Rng::new(seed),
+        }
+    }
+    /// Makes a particle moving in about the direction `dir` on top of `base_vel`
+    pub fn spawn(&mut self, kind: &ParticleKind, pos: Point2, dir: Vector2, base_vel: Vector2) {
+        let angle = angle_from_vec(&dir) + self.rng.range(-kind.spread, kind.spread);
+        let speed = self.rng.range(kind.speed.0, kind.speed.1);
+        let particle = Particle {
+            pos,
+            vel: base_vel + speed * angle_to_vec(angle),
+            age: 0.,
+            lifetime: self.rng.range(kind.lifetime.0, kind.lifetime.1),
+            start_color: kind.start_color,
+            end_color: kind.end_color,
+            start_size: kind.start_size,
+            end_size: kind.end_size,
+            alive: true,
+        };
+
+        if let Some(i) = self.free.pop() {
+            self.pool[i] = particle;
+        } else if self.pool.len() < MAX_PARTICLES {
+            self.pool.push(particle);
+        }
+    }
+    /// Makes `count` particles going in every direction
+    pub fn burst(&mut self, kind: &ParticleKind, pos: Point2, base_vel: Vector2, count: usize) {
+        for _ in 0..count {
+            let dir = angle_to_vec(self.rng.range(0., 2. * PI));
+            self.spawn(kind, pos, dir, base_vel);
+        }
+    }
+    /// Makes the particles for an effect in the world
+    pub fn effect(&mut self, effect: Effect) {
+        match effect {
+            Effect::Sparks(pos, vel) => self.burst(&SPARK, pos, vel, 8),
+            Effect::Debris { pos, vel, rad, color } => {
+                let kind = ParticleKind {
+                    start_color: color,
+                    end_color: Color { a: 0., .. color },
+                    .. DEBRIS
+                };
+                self.burst(&kind, pos, vel, rad as usize);
+            }
+        }
+    }
+    /// Moves the particles one tick forward and kills the old ones
+    pub fn update(&mut self) {
+        for (i, p) in self.pool.iter_mut().enumerate() {
+            if !p.alive {
+                continue
+            }
+            p.age += DELTA;
+            if p.age >= p.lifetime {
+                p.alive = false;
+                self.free.push(i);
+            } else {
+                p.pos += p.vel * DELTA;
+            }
+        }
+    }
+    /// Number of particles alive
+    pub fn count(&self) -> usize {
+        self.pool.len() - self.free.len()
+    }
+    /// Draws the particles in world coordinates with a batch of a small white image
+    pub fn draw(&self, ctx: &mut Context, batch: &mut SpriteBatch) -> GameResult<()> {
+        batch.clear();
+        for p in self.pool.iter().filter(|p| p.alive) {
+            let scale = p.size() / PARTICLE_IMAGE_SIZE as f32;
+            batch.add(graphics::DrawParam {
+                dest: p.pos,
+                offset: Point2::new(0.5, 0.5),
+                scale: Point2::new(scale, scale),
+                color: Some(p.color()),
+                .. Default::default()
+            });
+        }
+        graphics::set_color(ctx, graphics::WHITE)?;
+        graphics::draw(ctx, batch, Point2::origin(), 0.)
+    }
+}
+
+/// Makes the batch particles are drawn with
+pub fn particle_batch(ctx: &mut Context) -> GameResult<SpriteBatch> {
+    let img = graphics::Image::solid(ctx, PARTICLE_IMAGE_SIZE, graphics::WHITE)?;
+    Ok(SpriteBatch::new(img))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KIND: ParticleKind = ParticleKind {
+        lifetime: (0.5, 0.5),
+        .. EXHAUST
+    };
+
+    fn ticks(seconds: f32) -> usize {
+        (seconds / DELTA).ceil() as usize
+    }
+    /// Particles made by the exhaust of a ship in a second with its engine set to `usage`
+    fn exhaust_count(power: bool, usage: f64) -> usize {
+        let mut particles = Particles::new(1);
+        let mut emitter = Emitter::new(KIND, 60., 22.);
+        let mut ship = make_player(Point2::origin());
+        ship.thruster.power = power;
+        ship.thruster.throttle_usage = usage;
+        for _ in 0..ticks(1.) {
+            emitter.exhaust(&mut particles, &ship);
+        }
+        particles.count()
+    }
+
+    #[test]
+    fn lifetime_expiry() {
+        let mut particles = Particles::new(1);
+        particles.burst(&KIND, Point2::origin(), Vector2::new(0., 0.), 10);
+        assert_eq!(particles.count(), 10);
+        for _ in 1..ticks(0.5) {
+            particles.update();
+        }
+        assert_eq!(particles.count(), 10);
+        particles.update();
+        particles.update();
+        assert_eq!(particles.count(), 0);
+    }
+
+    #[test]
+    fn pool_reuse() {
+        let mut particles = Particles::new(1);
+        particles.burst(&KIND, Point2::origin(), Vector2::new(0., 0.), 10);
+        for _ in 0..ticks(0.5) + 1 {
+            particles.update();
+        }
+        assert_eq!(particles.free.len(), 10);
+
+        particles.burst(&KIND, Point2::origin(), Vector2::new(0., 0.), 6);
+        assert_eq!(particles.pool.len(), 10);
+        assert_eq!(particles.free.len(), 4);
+        assert_eq!(particles.count(), 6);
+    }
+
+    #[test]
+    fn pool_is_limited() {
+        let mut particles = Particles::new(1);
+        particles.burst(&KIND, Point2::origin(), Vector2::new(0., 0.), MAX_PARTICLES + 10);
+        assert_eq!(particles.count(), MAX_PARTICLES);
+    }
+
+    #[test]
+    fn exhaust_rate_scales_with_throttle() {
+        let normal = exhaust_count(true, NORMAL_USAGE);
+        assert!(normal >= 59 && normal <= 60, "{}", normal);
+        let double = exhaust_count(true, 2. * NORMAL_USAGE);
+        assert!(double >= 119 && double <= 120, "{}", double);
+        let half = exhaust_count(true, NORMAL_USAGE / 2.);
+        assert!(half >= 29 && half <= 30, "{}", half);
+        assert_eq!(exhaust_count(true, 0.), 0);
+        assert_eq!(exhaust_count(false, NORMAL_USAGE), 0);
+    }
+}
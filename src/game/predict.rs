@@ -0,0 +1,133 @@
+use ::*;
+use std::cmp::Ordering;
+use ggez::nalgebra as na;
+use super::world::World;
+use super::target::{Target, TargetKind};
+
+/// How many seconds ahead paths are predicted
+const PREDICT_TIME: f32 = 6.;
+/// Only objects this close to the player are simulated
+const PREDICT_RADIUS: f32 = 2000.;
+/// A point of the paths is recorded every this many ticks
+const SAMPLE_TICKS: usize = 4;
+/// Predictions are made again after this many ticks even if the controls stay the same
+const MAX_AGE: usize = 15;
+
+#[derive(Debug, Default)]
+/// Predicted paths of the player and the target
+pub struct Prediction {
+    /// Where the player will be
+    pub player: Vec<Point2>,
+    /// Where the target will be
+    pub target: Vec<Point2>,
+    /// Points where the player or the target will hit something
+    pub collisions: Vec<Point2>,
+    /// The input it was predicted with
+    input: InputState,
+    /// The throttle of the player when it was predicted
+    throttle: f64,
+    /// Ticks since it was predicted
+    age: usize,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// One of the bodies of a world
+enum Body {
+    Player,
+    Asteroid(usize),
+    Pickup(usize),
+}
+
+impl Prediction {
+    /// Simulates a copy of the world ahead as if the input stays the same
+    ///
+    /// The copy is updated with the same physics steps as the world, so autopilots,
+    /// throttle changes and collisions with nearby bodies are included
+    pub(super) fn new(world: &World, input: &InputState) -> Self {
+        let mut sim = world.copy_near(world.player.pos, PREDICT_RADIUS);
+        let mut prediction = Prediction {
+            input: *input,
+            throttle: world.player.thruster.throttle_usage,
+            .. Prediction::default()
+        };
+
+        let mut player_touching = false;
+        let mut target_touching = false;
+        let ticks = (PREDICT_TIME / DELTA) as usize;
+
+        for tick in 0..ticks {
+            sim.physics_update(input);
+
+            let hit = contact(&sim, sim.player.pos, sim.player.rad, Some(Body::Player));
+            if hit.is_some() && !player_touching {
+                prediction.collisions.extend(hit);
+            }
+            player_touching = hit.is_some();
+
+            if let Some(ref target) = sim.target {
+                let hit = contact(&sim, target.pos, target.rad, target_body(&sim, target));
+                if hit.is_some() && !target_touching {
+                    prediction.collisions.extend(hit);
+                }
+                target_touching = hit.is_some();
+            }
+
+            if tick % SAMPLE_TICKS == 0 {
+                prediction.player.push(sim.player.pos);
+                if let Some(ref target) = sim.target {
+                    prediction.target.push(target.pos);
+                }
+            }
+        }
+        prediction
+    }
+    /// Ages the prediction by a physics tick
+    pub(super) fn tick(&mut self) {
+        self.age += 1;
+    }
+    /// Whether it should be predicted again because the controls changed or it has gotten old
+    pub(super) fn is_stale(&self, world: &World, input: &InputState) -> bool {
+        self.age >= MAX_AGE || self.input != *input || self.throttle != world.player.thruster.throttle_usage
+    }
+    /// Draws the paths and collision points in world coordinates
+    pub fn draw(&self, ctx: &mut Context) -> GameResult<()> {
+        if self.player.len() >= 2 {
+            graphics::set_color(ctx, GREEN)?;
+            graphics::line(ctx, &self.player, 1.5)?;
+        }
+        if self.target.len() >= 2 {
+            graphics::set_color(ctx, YELLOW)?;
+            graphics::line(ctx, &self.target, 1.5)?;
+        }
+        graphics::set_color(ctx, RED)?;
+        for &p in &self.collisions {
+            graphics::circle(ctx, graphics::DrawMode::Line(2.), p, 6., 1.)?;
+        }
+        Ok(())
+    }
+}
+
+/// The body a target follows, which is the closest one in its collection
+fn target_body(world: &World, target: &Target) -> Option<Body> {
+    let closest = |objs: Vec<&Object>| objs.into_iter()
+        .map(|o| na::distance(&o.pos, &target.pos))
+        .enumerate()
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal))
+        .map(|(i, _)| i);
+    match target.kind {
+        TargetKind::Asteroid => closest(world.asteroids.iter().map(|a| a.as_obj()).collect()).map(Body::Asteroid),
+        TargetKind::Pickup => closest(world.pickups.iter().map(|p| p.as_obj()).collect()).map(Body::Pickup),
+    }
+}
+
+/// Finds where a circle touches an asteroid, pickup or the player, if it does
+///
+/// `body` is the body the circle is, which is skipped
+fn contact(world: &World, pos: Point2, rad: f32, body: Option<Body>) -> Option<Point2> {
+    world.asteroids.iter().enumerate().map(|(i, a)| (Body::Asteroid(i), a.as_obj()))
+        .chain(world.pickups.iter().enumerate().map(|(i, p)| (Body::Pickup(i), p.as_obj())))
+        .chain(Some((Body::Player, world.player.as_obj())))
+        .filter(|&(b, _)| Some(b) != body)
+        .find(|&(_, o)| na::distance(&o.pos, &pos) <= o.rad + rad)
+        .map(|(_, o)| pos + rad / (rad + o.rad) * (o.pos - pos))
+}
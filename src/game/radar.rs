@@ -0,0 +1,144 @@
+use ::*;
+use ggez::graphics::DrawMode;
+use super::world::World;
+
+/// Radius of the radar on the screen
+const RADIUS: f32 = 90.;
+/// Space between the radar and the edges of the screen
+const MARGIN: f32 = 10.;
+/// The ranges that can be zoomed between
+const RANGES: [f32; 4] = [500., 1000., 2000., 4000.];
+/// Smallest radius a blip is drawn with
+const MIN_BLIP: f32 = 1.5;
+
+/// Colour of the radar background
+const BACKGROUND: Color = Color{r:0.,g:0.,b:0.,a:0.6};
+/// Colour of asteroids on the radar
+const ASTEROID: Color = Color{r:0.7,g:0.7,b:0.7,a:1.};
+/// Colour of bullets on the radar
+const BULLET: Color = Color{r:1.,g:0.3,b:0.3,a:1.};
+/// Colour of the player on the radar
+const PLAYER: Color = Color{r:0.3,g:1.,b:0.3,a:1.};
+
+/// A minimap in the bottom right corner showing what's around the player
+pub struct Radar {
+    /// Whether it's drawn
+    pub visible: bool,
+    zoom: usize,
+    center: Point2,
+    range_text: PosText,
+}
+
+impl Radar {
+    /// Makes a radar for a screen of the given size
+    pub fn new(ctx: &mut Context, assets: &Assets, width: u32, height: u32) -> GameResult<Self> {
+        let zoom = 1;
+        let range_text = assets.text(ctx, Point2::origin(), &range_str(zoom))?;
+        let mut radar = Radar {
+            visible: true,
+            zoom,
+            center: Point2::origin(),
+            range_text,
+        };
+        radar.resize(width, height);
+        Ok(radar)
+    }
+    /// Moves it to the bottom right corner of a screen of the given size
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.center = Point2::new(width as f32 - RADIUS - MARGIN, height as f32 - RADIUS - MARGIN);
+        self.range_text.set_pos(self.center + Vector2::new(-RADIUS, -RADIUS - 18.));
+    }
+    /// The distance from the player at the edge of the radar
+    pub fn range(&self) -> f32 {
+        RANGES[self.zoom]
+    }
+    /// Shows a smaller area
+    pub fn zoom_in(&mut self, ctx: &mut Context, assets: &Assets) -> GameResult<()> {
+        if self.zoom > 0 {
+            self.zoom -= 1;
+            self.range_text.update_text(assets, ctx, &range_str(self.zoom))?;
+        }
+        Ok(())
+    }
+    /// Shows a larger area
+    pub fn zoom_out(&mut self, ctx: &mut Context, assets: &Assets) -> GameResult<()> {
+        if self.zoom + 1 < RANGES.len() {
+            self.zoom += 1;
+            self.range_text.update_text(assets, ctx, &range_str(self.zoom))?;
+        }
+        Ok(())
+    }
+    /// Draws the radar in screen coordinates
+    pub fn draw(&self, ctx: &mut Context, world: &World) -> GameResult<()> {
+        if !self.visible {
+            return Ok(())
+        }
+        graphics::set_color(ctx, BACKGROUND)?;
+        graphics::circle(ctx, DrawMode::Fill, self.center, RADIUS, 0.5)?;
+        graphics::set_color(ctx, TRANS)?;
+        graphics::circle(ctx, DrawMode::Line(1.), self.center, RADIUS, 0.5)?;
+        graphics::circle(ctx, DrawMode::Line(1.), self.center, RADIUS / 2., 0.5)?;
+
+        let origin = world.player.pos;
+        let scale = RADIUS / self.range();
+
+        graphics::set_color(ctx, ASTEROID)?;
+        for ast in world.asteroids.iter() {
+            self.draw_blip(ctx, origin, scale, ast.as_obj())?;
+        }
+        for pickup in world.pickups.iter() {
+            graphics::set_color(ctx, pickup.kind.tint())?;
+            self.draw_blip(ctx, origin, scale, &pickup.obj)?;
+        }
+        graphics::set_color(ctx, BULLET)?;
+        for bullet in world.bullets.iter() {
+            self.draw_blip(ctx, origin, scale, bullet)?;
+        }
+
+        // Circle the target and mark the current objective
+        if let Some(ref target) = world.target {
+            if let Some(p) = self.to_radar(origin, scale, target.pos) {
+                graphics::set_color(ctx, YELLOW)?;
+                graphics::circle(ctx, DrawMode::Line(1.), p, (target.rad * scale).max(MIN_BLIP) + 3., 0.5)?;
+            }
+        }
+        if let Some(ref mission) = world.mission {
+            if let Some(p) = mission.target_pos(&world.asteroids).and_then(|p| self.to_radar(origin, scale, p)) {
+                graphics::set_color(ctx, BLUE)?;
+                graphics::circle(ctx, DrawMode::Fill, p, 3., 0.5)?;
+            }
+        }
+
+        // The player is a triangle pointing along the heading
+        let dir = angle_to_vec(world.player.rot);
+        let side = Vector2::new(-dir.y, dir.x);
+        let c = self.center;
+        let points = [c + 7. * dir, c - 4. * dir + 4. * side, c - 4. * dir - 4. * side];
+        graphics::set_color(ctx, PLAYER)?;
+        graphics::polygon(ctx, DrawMode::Fill, &points)?;
+
+        graphics::set_color(ctx, graphics::WHITE)?;
+        self.range_text.draw_text(ctx)
+    }
+    /// Where a point in the world is on the radar, if it's within range
+    fn to_radar(&self, origin: Point2, scale: f32, p: Point2) -> Option<Point2> {
+        let rel = (p - origin) * scale;
+        if rel.norm() <= RADIUS {
+            Some(self.center + rel)
+        } else {
+            None
+        }
+    }
+    /// Draws an object as a dot in the current colour
+    fn draw_blip(&self, ctx: &mut Context, origin: Point2, scale: f32, obj: &Object) -> GameResult<()> {
+        match self.to_radar(origin, scale, obj.pos) {
+            Some(p) => graphics::circle(ctx, DrawMode::Fill, p, (obj.rad * scale).max(MIN_BLIP), 0.5),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Text showing the range of a zoom level
+fn range_str(zoom: usize) -> String {
+    format!("Radar: {} px", RANGES[zoom])
+}
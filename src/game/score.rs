@@ -0,0 +1,209 @@
+use ::*;
+
+/// Points for every second survived
+const SURVIVAL_POINTS: f32 = 1.;
+/// Points for each collected fuel canister
+const FUEL_POINTS: u32 = 25;
+/// How many entries are kept in the high-score table for each mode
+const MAX_ENTRIES: usize = 10;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+/// The kind of game a score was achieved in
+pub enum Mode {
+    /// Free flight where the player spawns things themselves
+    Sandbox,
+    /// Playing through a mission
+    Mission,
+}
+
+impl Mode {
+    /// Name of the mode as shown in the high-score table
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Mode::Sandbox => "Sandbox",
+            Mode::Mission => "Mission",
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+/// Something that happened in the world worth points
+///
+/// There are no enemies yet, so killing them isn't worth anything until there are
+pub enum ScoreEvent {
+    /// An asteroid with the given radius was destroyed
+    AsteroidDestroyed(f32),
+    /// A fuel canister was collected
+    FuelCollected,
+    /// Extra points, like from a pickup
+    Bonus(u32),
+    /// The player survived for the given amount of seconds
+    Survived(f32),
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+/// The score of the current game
+pub struct Score {
+    /// Points from destroyed asteroids, fuel and bonuses
+    pub points: u32,
+    /// Seconds the player has been alive
+    pub survival_time: f32,
+    /// How many asteroids have been destroyed
+    pub asteroids_destroyed: u32,
+    /// How many fuel canisters have been collected
+    pub fuel_collected: u32,
+}
+
+impl Score {
+    /// Register a scoring event
+    pub fn add(&mut self, event: ScoreEvent) {
+        match event {
+            ScoreEvent::AsteroidDestroyed(rad) => {
+                self.asteroids_destroyed += 1;
+                self.points += asteroid_points(rad);
+            }
+            ScoreEvent::FuelCollected => {
+                self.fuel_collected += 1;
+                self.points += FUEL_POINTS;
+            }
+            ScoreEvent::Bonus(points) => self.points += points,
+            ScoreEvent::Survived(t) => self.survival_time += t,
+        }
+    }
+    /// The total score including the points for the survival time
+    pub fn total(&self) -> u32 {
+        self.points + (SURVIVAL_POINTS * self.survival_time) as u32
+    }
+}
+
+/// Points given for destroying an asteroid of a given radius
+///
+/// Bigger asteroids take more hits, so they're worth more
+pub fn asteroid_points(rad: f32) -> u32 {
+    (5. * rad).round() as u32
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// An entry in the high-score table
+pub struct HighScore {
+    /// Name entered by the player
+    pub name: String,
+    /// The mode the score was achieved in
+    pub mode: Mode,
+    /// The total score
+    pub score: u32,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+/// The local high-score table
+///
+/// Entries are kept ordered by mode and then by score from highest to lowest
+pub struct HighScores {
+    entries: Vec<HighScore>,
+}
+
+impl HighScores {
+    /// The entries for one mode, best first
+    pub fn for_mode<'a>(&'a self, mode: Mode) -> Box<Iterator<Item=&'a HighScore> + 'a> {
+        Box::new(self.entries.iter().filter(move |e| e.mode == mode))
+    }
+    /// Checks whether a score would make it into the table
+    pub fn qualifies(&self, mode: Mode, score: u32) -> bool {
+        let count = self.for_mode(mode).count();
+        count < MAX_ENTRIES || self.for_mode(mode).any(|e| e.score < score)
+    }
+    /// Inserts a new entry keeping the table ordered
+    ///
+    /// Only the best `MAX_ENTRIES` of each mode are kept
+    pub fn insert(&mut self, name: String, mode: Mode, score: u32) {
+        self.entries.push(HighScore {
+            name,
+            mode,
+            score,
+        });
+        self.entries.sort_by(|a, b| a.mode.cmp(&b.mode).then(b.score.cmp(&a.score)));
+
+        let mut count = 0;
+        let mut last_mode = None;
+        self.entries.retain(|e| {
+            if last_mode != Some(e.mode) {
+                last_mode = Some(e.mode);
+                count = 0;
+            }
+            count += 1;
+            count <= MAX_ENTRIES
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scores(table: &HighScores, mode: Mode) -> Vec<u32> {
+        table.for_mode(mode).map(|e| e.score).collect()
+    }
+
+    #[test]
+    fn ordered_per_mode() {
+        let mut table = HighScores::default();
+        table.insert("A".to_owned(), Mode::Mission, 20);
+        table.insert("B".to_owned(), Mode::Sandbox, 50);
+        table.insert("C".to_owned(), Mode::Mission, 70);
+        table.insert("D".to_owned(), Mode::Sandbox, 10);
+
+        assert_eq!(scores(&table, Mode::Sandbox), vec![50, 10]);
+        assert_eq!(scores(&table, Mode::Mission), vec![70, 20]);
+        // Sandbox comes before missions in the table
+        assert_eq!(table.entries[0].mode, Mode::Sandbox);
+        assert_eq!(table.entries[2].mode, Mode::Mission);
+    }
+
+    #[test]
+    fn truncated_per_mode() {
+        let mut table = HighScores::default();
+        for i in 0..MAX_ENTRIES as u32 + 5 {
+            table.insert(format!("{}", i), Mode::Sandbox, i);
+        }
+        table.insert("M".to_owned(), Mode::Mission, 1);
+
+        let sandbox = scores(&table, Mode::Sandbox);
+        assert_eq!(sandbox.len(), MAX_ENTRIES);
+        assert_eq!(sandbox[0], MAX_ENTRIES as u32 + 4);
+        assert_eq!(*sandbox.last().unwrap(), 5);
+        // A full table for one mode doesn't push out the other
+        assert_eq!(scores(&table, Mode::Mission), vec![1]);
+    }
+
+    #[test]
+    fn qualifies() {
+        let mut table = HighScores::default();
+        assert!(table.qualifies(Mode::Sandbox, 0));
+        for _ in 0..MAX_ENTRIES {
+            table.insert("A".to_owned(), Mode::Sandbox, 100);
+        }
+        assert!(table.qualifies(Mode::Sandbox, 101));
+        assert!(!table.qualifies(Mode::Sandbox, 99));
+        // The other mode still has room
+        assert!(table.qualifies(Mode::Mission, 0));
+    }
+
+    #[test]
+    fn ties() {
+        let mut table = HighScores::default();
+        table.insert("First".to_owned(), Mode::Sandbox, 100);
+        table.insert("Second".to_owned(), Mode::Sandbox, 100);
+        // The score that was there first stays ahead
+        let names: Vec<_> = table.for_mode(Mode::Sandbox).map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["First", "Second"]);
+
+        for _ in 2..MAX_ENTRIES {
+            table.insert("Other".to_owned(), Mode::Sandbox, 100);
+        }
+        // Tying the lowest score of a full table isn't enough
+        assert!(!table.qualifies(Mode::Sandbox, 100));
+        table.insert("Late".to_owned(), Mode::Sandbox, 100);
+        assert!(table.for_mode(Mode::Sandbox).all(|e| e.name != "Late"));
+        assert_eq!(table.for_mode(Mode::Sandbox).count(), MAX_ENTRIES);
+    }
+}
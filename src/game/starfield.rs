@@ -0,0 +1,159 @@
+use std::f32::consts::PI;
+
+use ::*;
+use ggez::graphics::{Image, SpriteBatch};
+use rng::Rng;
+use super::camera::Camera;
+
+/// Width and height of a chunk of stars in the coordinates of its layer
+const STAR_CHUNK: f32 = 512.;
+/// Size of the square image stars are drawn with
+const STAR_IMAGE_SIZE: u16 = 4;
+/// Width and height of the image nebulae are drawn with
+const NEBULA_IMAGE_SIZE: u16 = 64;
+/// Number of clouds a nebula is made of
+const NEBULA_CLOUDS: usize = 6;
+/// How much of their brightness twinkling stars lose at most
+const TWINKLE: f32 = 0.5;
+
+/// Colours of stars from hot to cold
+const STAR_COLORS: [(f32, f32, f32); 4] = [
+    (0.7, 0.8, 1.),
+    (1., 1., 1.),
+    (1., 0.95, 0.7),
+    (1., 0.7, 0.6),
+];
+/// Colours of nebulae
+const NEBULA_COLORS: [(f32, f32, f32); 3] = [
+    (0.5, 0.3, 1.),
+    (0.2, 0.6, 1.),
+    (1., 0.3, 0.5),
+];
+
+#[derive(Debug, Clone, Deserialize)]
+/// A layer of generated stars in a background
+pub struct StarLayer {
+    /// How much it moves and zooms with the camera, 0 is not at all and 1 is like the world
+    pub parallax: f32,
+    /// Number of stars in each chunk
+    pub density: u32,
+    /// Chance of a chunk having a nebula
+    #[serde(default)]
+    pub nebula: f32,
+}
+
+/// Draws stars and nebulae generated from the world seed
+///
+/// The sky is made again from the seed every frame for the chunks that can be seen,
+/// so it looks the same every time the same place is seen
+pub struct Starfield {
+    /// Seconds of twinkling
+    time: f32,
+    stars: SpriteBatch,
+    nebulae: SpriteBatch,
+}
+
+/// A round cloud that fades out towards the edge
+fn nebula_image(ctx: &mut Context) -> GameResult<Image> {
+    let size = NEBULA_IMAGE_SIZE as usize;
+    let mut pixels = Vec::with_capacity(size * size * 4);
+    for y in 0..size {
+        for x in 0..size {
+            let dx = (x as f32 + 0.5) / size as f32 * 2. - 1.;
+            let dy = (y as f32 + 0.5) / size as f32 * 2. - 1.;
+            let fade = (1. - (dx * dx + dy * dy).sqrt()).max(0.);
+            pixels.extend_from_slice(&[255, 255, 255, (255. * fade * fade) as u8]);
+        }
+    }
+    Image::from_rgba8(ctx, NEBULA_IMAGE_SIZE, NEBULA_IMAGE_SIZE, &pixels)
+}
+
+impl Starfield {
+    /// Makes the images the stars and nebulae are drawn with
+    pub fn new(ctx: &mut Context) -> GameResult<Self> {
+        Ok(Starfield {
+            time: 0.,
+            stars: SpriteBatch::new(Image::solid(ctx, STAR_IMAGE_SIZE, graphics::WHITE)?),
+            nebulae: SpriteBatch::new(nebula_image(ctx)?),
+        })
+    }
+    /// Advances the twinkling by a tick
+    pub fn tick(&mut self) {
+        self.time += DELTA;
+    }
+    /// Draws the layers as seen through the camera, generated from `seed`
+    pub fn draw(&mut self, ctx: &mut Context, layers: &[StarLayer], camera: &Camera, seed: u64) -> GameResult<()> {
+        for layer in layers {
+            let view = camera.parallax(layer.parallax);
+            // One more chunk around the view since nebulae reach out of their chunks
+            let rect = view.view_rect();
+            let rect = graphics::Rect::new(rect.x - STAR_CHUNK, rect.y - STAR_CHUNK, rect.w + 2. * STAR_CHUNK, rect.h + 2. * STAR_CHUNK);
+            let (x0, x1) = ((rect.x / STAR_CHUNK).floor() as i32, ((rect.x + rect.w) / STAR_CHUNK).ceil() as i32);
+            let (y0, y1) = ((rect.y / STAR_CHUNK).floor() as i32, ((rect.y + rect.h) / STAR_CHUNK).ceil() as i32);
+
+            // Layers with different depths get different stars
+            let layer_seed = seed ^ u64::from(layer.parallax.to_bits()).wrapping_mul(0xD6E8_FEB8_6659_FD93);
+
+            self.stars.clear();
+            self.nebulae.clear();
+            for cx in x0..x1 {
+                for cy in y0..y1 {
+                    let mut rng = Rng::with_coords(layer_seed, cx, cy);
+                    let origin = Point2::new(cx as f32 * STAR_CHUNK, cy as f32 * STAR_CHUNK);
+                    if rng.chance(layer.nebula) {
+                        self.add_nebula(&mut rng, origin);
+                    }
+                    for _ in 0..layer.density {
+                        self.add_star(&mut rng, origin);
+                    }
+                }
+            }
+
+            graphics::push_transform(ctx, Some(view.transform()));
+            graphics::apply_transformations(ctx)?;
+            graphics::draw(ctx, &self.nebulae, Point2::origin(), 0.)?;
+            graphics::draw(ctx, &self.stars, Point2::origin(), 0.)?;
+            graphics::pop_transform(ctx);
+            graphics::apply_transformations(ctx)?;
+        }
+        Ok(())
+    }
+    /// Adds a star somewhere in the chunk at `origin`
+    fn add_star(&mut self, rng: &mut Rng, origin: Point2) {
+        let pos = origin + Vector2::new(rng.range(0., STAR_CHUNK), rng.range(0., STAR_CHUNK));
+        let size = rng.range(1., 3.);
+        let brightness = rng.range(0.3, 1.);
+        let (r, g, b) = STAR_COLORS[rng.range_u32(0, STAR_COLORS.len() as u32) as usize];
+        let phase = rng.range(0., 2. * PI);
+        let speed = rng.range(1., 4.);
+
+        let twinkle = 1. - TWINKLE * (0.5 + 0.5 * (self.time * speed + phase).sin());
+        let a = brightness * twinkle;
+        let scale = size / STAR_IMAGE_SIZE as f32;
+        self.stars.add(graphics::DrawParam {
+            dest: pos,
+            offset: Point2::new(0.5, 0.5),
+            scale: Point2::new(scale, scale),
+            color: Some(Color{r,g,b,a}),
+            .. Default::default()
+        });
+    }
+    /// Adds a nebula made of a few overlapping clouds somewhere in the chunk at `origin`
+    fn add_nebula(&mut self, rng: &mut Rng, origin: Point2) {
+        let center = origin + Vector2::new(rng.range(0., STAR_CHUNK), rng.range(0., STAR_CHUNK));
+        let (r, g, b) = NEBULA_COLORS[rng.range_u32(0, NEBULA_COLORS.len() as u32) as usize];
+        for _ in 0..NEBULA_CLOUDS {
+            let pos = center + Vector2::new(rng.range(-150., 150.), rng.range(-150., 150.));
+            let scale = rng.range(150., 400.) / NEBULA_IMAGE_SIZE as f32;
+            let a = rng.range(0.05, 0.15);
+            self.nebulae.add(graphics::DrawParam {
+                dest: pos,
+                rotation: rng.range(0., 2. * PI),
+                offset: Point2::new(0.5, 0.5),
+                scale: Point2::new(scale, scale),
+                color: Some(Color{r,g,b,a}),
+                .. Default::default()
+            });
+        }
+    }
+}
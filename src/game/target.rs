@@ -0,0 +1,147 @@
+use ::*;
+use std::cmp::Ordering;
+use ggez::nalgebra as na;
+use super::world::Objects;
+
+/// Objects further away than this can't be targeted by cycling
+const MAX_TARGET_DISTANCE: f32 = 3000.;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// Which collection a target is in
+pub enum TargetKind {
+    /// In the asteroids
+    Asteroid,
+    /// In the pickups
+    Pickup,
+}
+
+impl TargetKind {
+    /// Name shown in the HUD
+    pub fn name(&self) -> &'static str {
+        match *self {
+            TargetKind::Asteroid => "Asteroid",
+            TargetKind::Pickup => "Pickup",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// The object the player has selected
+///
+/// Objects don't have identities, so the target remembers where its object was
+/// and finds it again every tick by where it should have moved to.
+pub struct Target {
+    /// The collection of the object
+    pub kind: TargetKind,
+    #[serde(serialize_with = "::save::point_ser", deserialize_with = "::save::point_des")]
+    /// Last known position
+    pub pos: Point2,
+    #[serde(serialize_with = "::save::vec_ser", deserialize_with = "::save::vec_des")]
+    /// Last known velocity
+    pub vel: Vector2,
+    /// Radius of the object
+    pub rad: f32,
+}
+
+impl Target {
+    fn new(kind: TargetKind, obj: &Object) -> Self {
+        Target {
+            kind,
+            pos: obj.pos,
+            vel: obj.vel,
+            rad: obj.rad,
+        }
+    }
+    /// Finds the object again after a physics update
+    ///
+    /// Returns `false` if it's gone
+    pub(super) fn reacquire(&mut self, asteroids: &Objects<Asteroid>, pickups: &Objects<Pickup>) -> bool {
+        let predicted = self.pos + self.vel * DELTA;
+        let kind = self.kind;
+        let found = candidates(asteroids, pickups)
+            .into_iter()
+            .filter(|&(k, _)| k == kind)
+            .map(|(_, o)| (na::distance(&o.pos, &predicted), o))
+            .filter(|&(dist, o)| dist <= o.rad)
+            .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal))
+            .map(|(_, o)| Target::new(kind, o));
+
+        match found {
+            Some(t) => {
+                *self = t;
+                true
+            }
+            None => false,
+        }
+    }
+    /// How the target moves relative to an object
+    pub fn relative_to(&self, obj: &Object) -> RelativeMotion {
+        let r = self.pos - obj.pos;
+        let v = self.vel - obj.vel;
+        let distance = r.norm();
+        let rel_speed2 = v.norm_squared();
+
+        // The time when the distance is the smallest
+        let tca = if rel_speed2 > 0. { -na::dot(&r, &v) / rel_speed2 } else { 0. };
+        let tca = if tca > 0. { Some(tca) } else { None };
+
+        RelativeMotion {
+            distance,
+            closing_speed: if distance > 0. { -na::dot(&r, &v) / distance } else { 0. },
+            rel_vel: v,
+            tca,
+            ca_distance: (r + v * tca.unwrap_or(0.)).norm(),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+/// Motion of a target relative to the player
+pub struct RelativeMotion {
+    /// Distance between the centres
+    pub distance: f32,
+    /// How fast the distance is shrinking (negative when moving apart)
+    pub closing_speed: f32,
+    /// Velocity of the target as seen from the player
+    pub rel_vel: Vector2,
+    /// Seconds until the closest approach, if it's in the future
+    pub tca: Option<f32>,
+    /// Distance at the closest approach
+    pub ca_distance: f32,
+}
+
+/// All the objects that can be targeted
+fn candidates<'a>(asteroids: &'a Objects<Asteroid>, pickups: &'a Objects<Pickup>) -> Vec<(TargetKind, &'a Object)> {
+    asteroids.iter().map(|a| (TargetKind::Asteroid, a.as_obj()))
+        .chain(pickups.iter().map(|p| (TargetKind::Pickup, p.as_obj())))
+        .collect()
+}
+
+/// Picks the next target by distance from `from` after the current one
+pub(super) fn cycle(current: Option<&Target>, from: Point2, asteroids: &Objects<Asteroid>, pickups: &Objects<Pickup>) -> Option<Target> {
+    let mut objs: Vec<_> = candidates(asteroids, pickups)
+        .into_iter()
+        .map(|(k, o)| (na::distance(&o.pos, &from), k, o))
+        .filter(|&(dist, _, _)| dist <= MAX_TARGET_DISTANCE)
+        .collect();
+    objs.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+
+    // Find where the current target is in the list and take the one after it
+    let next = current
+        .and_then(|t| objs.iter().position(|&(_, k, o)| k == t.kind && na::distance(&o.pos, &t.pos) < 1.))
+        .map(|i| i + 1)
+        .unwrap_or(0);
+
+    objs.get(next).or_else(|| objs.first()).map(|&(_, k, o)| Target::new(k, o))
+}
+
+/// Picks the object under a point in the world
+pub(super) fn pick(p: Point2, asteroids: &Objects<Asteroid>, pickups: &Objects<Pickup>) -> Option<Target> {
+    candidates(asteroids, pickups)
+        .into_iter()
+        .map(|(k, o)| (na::distance(&o.pos, &p), k, o))
+        // Be a little lenient so small objects are easier to click
+        .filter(|&(dist, _, o)| dist <= o.rad + 10.)
+        .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal))
+        .map(|(_, k, o)| Target::new(k, o))
+}
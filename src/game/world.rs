@@ -1,22 +1,75 @@
 use ::*;
+use ggez::nalgebra as na;
 use self_compare::SliceCompareExt;
+use super::score::{Score, ScoreEvent};
+use super::mission::{MissionProgress, MissionEvent};
+use super::gen::Field;
+use super::target::Target;
 
 #[derive(Debug, Serialize, Deserialize)]
 /// All the objects in the current world
 pub struct World {
     pub(super) player: ThrustedObj,
-    pub(super) asteroids: Objects<DestructableObj>,
-    pub(super) fuels: Objects<Object>,
+    pub(super) asteroids: Objects<Asteroid>,
+    pub(super) pickups: Objects<Pickup>,
     pub(super) bullets: Objects<Object>,
+    pub(super) loadout: Loadout,
+    pub(super) score: Score,
+    pub(super) mission: Option<MissionProgress>,
+    pub(super) field: Field,
+    pub(super) target: Option<Target>,
+    #[serde(skip)]
+    pub(super) effects: Vec<Effect>,
+    /// How many pairs of objects were checked for collisions in the last tick
+    #[serde(skip)]
+    pub(super) collision_pairs: usize,
 }
 
-use std::ops::{Deref, DerefMut};
+#[derive(Debug, Copy, Clone)]
+/// Something that happened in the world that should be shown with particles
+pub enum Effect {
+    /// A bullet hit something at the position moving with the velocity
+    Sparks(Point2, Vector2),
+    /// An asteroid broke apart
+    Debris {
+        /// Where it was
+        pos: Point2,
+        /// How it was moving
+        vel: Vector2,
+        /// Its radius
+        rad: f32,
+        /// The colour of what it was made of
+        color: Color,
+    },
+}
 
-#[derive(Debug, Serialize, Deserialize)]
+use std::ops::{Deref, DerefMut, AddAssign};
+use ggez::graphics::{Rect, SpriteBatch};
+
+#[derive(Debug, Default, Copy, Clone)]
+/// How many objects were drawn and how many were skipped for being off screen
+pub struct DrawStats {
+    /// Objects drawn
+    pub drawn: usize,
+    /// Objects skipped
+    pub culled: usize,
+}
+
+impl AddAssign for DrawStats {
+    fn add_assign(&mut self, oth: Self) {
+        self.drawn += oth.drawn;
+        self.culled += oth.culled;
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// A collection of objects with the same sprite face
 pub struct Objects<T: AsObject> {
     inner: Vec<T>,
     sprite: Sprite,
+    /// Batch the objects are drawn with, kept between frames
+    #[serde(skip)]
+    batch: Option<SpriteBatch>,
 }
 
 impl<T: AsObject> Objects<T> {
@@ -25,16 +78,50 @@ impl<T: AsObject> Objects<T> {
     pub fn new(v: Vec<T>, sprite: Sprite) -> Self {
         Objects {
             inner: v,
-            sprite
+            sprite,
+            batch: None,
         }
     }
-    /// Draw all objects
-    #[inline]
-    pub fn draw(&self, ctx: &mut Context, assets: &Assets) -> GameResult<()> {
+    /// Draw all objects that can be seen in `view` with one sprite batch
+    ///
+    /// The sprite is scaled to fit the radius of each object
+    pub fn draw(&mut self, ctx: &mut Context, assets: &Assets, view: &Rect) -> GameResult<DrawStats> {
+        let img = assets.get_img(self.sprite);
+        let info = assets.info(self.sprite);
+        let offset = info.offset();
+        let sprite_rad = info.draw_radius();
+        // Half the diagonal of the sprite, so rotated sprites aren't cut off at the edges
+        let half_size = info.width.max(info.height) * 0.71;
+
+        // The image is given when drawing, so the batch keeps working after the assets are reloaded
+        let batch = self.batch.get_or_insert_with(|| SpriteBatch::new(img.clone()));
+        batch.clear();
+        let mut stats = DrawStats::default();
         for obj in &self.inner {
-            obj.as_obj().draw(ctx, assets.get_img(self.sprite))?;
+            let obj_ref = obj.as_obj();
+            let scale = obj_ref.rad / sprite_rad;
+            let margin = scale * half_size;
+            let p = obj_ref.pos;
+            if p.x + margin < view.x || p.x - margin > view.x + view.w
+                || p.y + margin < view.y || p.y - margin > view.y + view.h {
+                stats.culled += 1;
+                continue
+            }
+            stats.drawn += 1;
+            batch.add(graphics::DrawParam {
+                src: info.src(img, info.frame_at(obj_ref.anim_time)),
+                dest: p,
+                rotation: obj_ref.rot,
+                offset,
+                scale: Point2::new(scale, scale),
+                color: obj.color(),
+                .. Default::default()
+            });
         }
-        Ok(())
+        if stats.drawn > 0 {
+            graphics::draw(ctx, &batch.with_image(img), Point2::origin(), 0.)?;
+        }
+        Ok(stats)
     }
     /// Draw lines of all objects
     #[inline]
@@ -45,9 +132,14 @@ impl<T: AsObject> Objects<T> {
         Ok(())
     }
     #[inline]
-    /// Hanldes collision of objects in the collection with each other
-    fn self_collision(&mut self) {
-        self.inner.compare_self_mut(|a, b| check_and_resolve(a.as_obj_mut(), b.as_obj_mut()));
+    /// Hanldes collision of objects in the collection with each other and returns how many pairs were checked
+    fn self_collision(&mut self) -> usize {
+        let mut pairs = 0;
+        self.inner.compare_self_mut(|a, b| {
+            pairs += 1;
+            check_and_resolve(a.as_obj_mut(), b.as_obj_mut())
+        });
+        pairs
     }
     #[inline]
     /// Update, compare and remove spat out indices
@@ -70,6 +162,14 @@ impl<T: AsObject> Objects<T> {
             f(obj)
         }
     }
+    /// Copies the objects that are within `radius` of `center`
+    fn copy_near(&self, center: Point2, radius: f32) -> Self where T: Clone {
+        Objects {
+            inner: self.inner.iter().filter(|o| na::distance(&o.as_obj().pos, &center) <= radius).cloned().collect(),
+            sprite: self.sprite,
+            batch: None,
+        }
+    }
 }
 
 impl<T: AsObject> Deref for Objects<T> {
@@ -103,64 +203,181 @@ impl World {
         let &mut World {
             ref mut player,
             ref mut asteroids,
-            ref mut fuels,
+            ref mut pickups,
             ref mut bullets,
+            ref mut loadout,
+            ref mut score,
+            ref mut mission,
+            field: _,
+            ref mut target,
+            ref mut effects,
+            ref mut collision_pairs,
         } = self;
 
-        player.obj.rot += 1.7 * input_state.hor() * DELTA;
+        // Every pair of objects checked for a collision this tick
+        let mut pairs = 0;
+
+        if !player.is_dead() {
+            score.add(ScoreEvent::Survived(DELTA));
+        }
 
-        player.thruster.power = input_state.ver() == 1.;
+        // Steering by hand turns off the autopilot
+        if input_state.is_steering() {
+            player.autopilot = None;
+        }
+        player.controls = match player.autopilot {
+            Some(autopilot) => autopilot.controls(player, target.as_ref().map(|t| t.vel)),
+            None => input_state.controls(),
+        };
         player.thruster.throttle(input_state.throttle() as f64 * 17. * DDELTA);
 
         player.update();
 
-        let consumed_fuel = fuels.iterate_rmv_indices(|fuel| {
-            fuel.update();
-            if player.collides(&fuel) {
-                if (fuel.vel - player.vel).norm() <= 30. {
+        let mut collected = Vec::new();
+        pickups.iterate_rmv_indices(|pickup| {
+            pickup.update();
+            pairs += 1;
+            if player.collides(&pickup.obj) {
+                if pickup.can_capture(player) {
+                    collected.push((pickup.kind, pickup.amount));
                     true
                 } else {
-                    resolve(player, fuel);
+                    resolve(player, &mut pickup.obj);
                     false
                 }
             } else {
                 false
             }
         });
-        player.thruster.fuel += 200. * consumed_fuel as f64;
+        for (kind, amount) in collected {
+            match kind {
+                PickupKind::Fuel => {
+                    player.thruster.fuel += amount as f64;
+                    player.update_mass();
+                    score.add(ScoreEvent::FuelCollected);
+                }
+                PickupKind::Repair => player.health = (player.health + amount).min(PLAYER_HEALTH),
+                PickupKind::Ammo => loadout.ammo += amount as u32,
+                PickupKind::Score => score.add(ScoreEvent::Bonus(amount as u32)),
+                PickupKind::Weapon(weapon) => loadout.unlock(weapon),
+            }
+        }
         asteroids.iterate(|ast| {
             ast.update();
+            pairs += 1;
             check_and_resolve(player, ast);
         });
 
         bullets.iterate_rmv_indices(|bullet| {
             bullet.update();
+            pairs += 1;
             if player.collides(&bullet) {
                 player.hit(5.);
+                effects.push(Effect::Sparks(bullet.pos, player.vel));
                 true
             } else {
                 for ast in asteroids.iter_mut() {
+                    pairs += 1;
                     if ast.collides(&bullet) {
                         ast.hit(5.);
+                        effects.push(Effect::Sparks(bullet.pos, ast.vel));
                         return true;
                     }
                 }
                 false
             }
         });
+        for ast in asteroids.iter().filter(|ast| ast.is_dead()) {
+            score.add(ScoreEvent::AsteroidDestroyed(ast.rad));
+            effects.push(Effect::Debris {
+                pos: ast.pos,
+                vel: ast.vel,
+                rad: ast.rad,
+                color: ast.material.tint(),
+            });
+        }
         asteroids.retain(|ast| !ast.is_dead());
 
-        bullets.self_collision();
-        asteroids.self_collision();
-        fuels.self_collision();
+        pairs += bullets.self_collision();
+        pairs += asteroids.self_collision();
+        pairs += pickups.self_collision();
 
-        fuels.iterate(|fuel| {
+        pickups.iterate(|pickup| {
             asteroids.iterate(|ast| {
-                check_and_resolve(fuel, ast);
+                pairs += 1;
+                check_and_resolve(&mut pickup.obj, ast);
             });
             bullets.iterate(|bul| {
-                check_and_resolve(fuel, bul);
+                pairs += 1;
+                check_and_resolve(&mut pickup.obj, bul);
             });
         });
+        *collision_pairs = pairs;
+
+        if let Some(ref mut mission) = *mission {
+            mission.update(player, asteroids, score);
+        }
+
+        // Forget the target if it's gone
+        let lost = match *target {
+            Some(ref mut t) => !t.reacquire(asteroids, pickups),
+            None => false,
+        };
+        if lost {
+            *target = None;
+        }
+    }
+    /// Makes a copy of the world with only the objects within `radius` of `center`
+    ///
+    /// The copy has no mission and no frozen chunks, so updating it can't affect this world
+    pub(super) fn copy_near(&self, center: Point2, radius: f32) -> World {
+        World {
+            player: self.player.clone(),
+            asteroids: self.asteroids.copy_near(center, radius),
+            pickups: self.pickups.copy_near(center, radius),
+            bullets: self.bullets.copy_near(center, radius),
+            loadout: self.loadout.clone(),
+            score: self.score.clone(),
+            mission: None,
+            field: Field::new(self.field.seed()),
+            target: self.target.clone(),
+            effects: Vec::new(),
+            collision_pairs: 0,
+        }
+    }
+    /// Generates and freezes chunks of the asteroid field around `center`
+    pub(super) fn update_chunks(&mut self, center: Point2) {
+        self.field.update(center, &mut self.asteroids, &mut self.pickups);
+    }
+    /// Fires the selected weapon of the player
+    pub(super) fn fire(&mut self) {
+        let bullets = self.loadout.fire(&self.player);
+        self.bullets.extend(bullets);
+    }
+    /// The total momentum and kinetic energy of everything in the world
+    pub(super) fn momentum_and_energy(&self) -> (Vector2, f32) {
+        let objs = Some(self.player.as_obj()).into_iter()
+            .chain(self.asteroids.iter().map(|a| a.as_obj()))
+            .chain(self.pickups.iter().map(|p| p.as_obj()))
+            .chain(self.bullets.iter());
+
+        let mut momentum = Vector2::new(0., 0.);
+        let mut energy = 0.;
+        for obj in objs {
+            momentum += obj.mass * obj.vel;
+            energy += 0.5 * obj.mass * obj.vel.norm_squared();
+        }
+        (momentum, energy)
+    }
+    /// Takes the effects that have happened since last time
+    pub(super) fn take_effects(&mut self) -> Vec<Effect> {
+        ::std::mem::replace(&mut self.effects, Vec::new())
+    }
+    /// Takes the events of the current mission since last time
+    pub(super) fn mission_events(&mut self) -> Vec<MissionEvent> {
+        match self.mission {
+            Some(ref mut mission) => mission.take_events(),
+            None => Vec::new(),
+        }
     }
 }
@@ -8,6 +8,11 @@ extern crate bincode;
 extern crate serde_derive;
 extern crate serde;
 extern crate self_compare;
+extern crate toml;
+extern crate gfx;
+extern crate gfx_device_gl;
+extern crate gfx_gl;
+extern crate image;
 
 // use ggez::audio;
 use ggez::conf;
@@ -19,12 +24,21 @@ use ggez::graphics::{self, Vector2, Point2, Matrix4, Color};
 mod obj;
 pub use obj::*;
 pub use obj::phys::*;
+pub use obj::asteroid::*;
+pub use obj::pickup::*;
+pub use obj::weapon::*;
+pub use obj::autopilot::*;
 mod tex;
 pub use tex::*;
 mod ext;
 pub use ext::*;
 mod game;
 pub use game::*;
+mod scene;
+pub use scene::*;
+pub use scene::menu::*;
+mod ui;
+pub use ui::{Ui, Anchor};
 
 /// Makes a unit vector from a given direction angle
 fn angle_to_vec(angle: f32) -> Vector2 {
@@ -47,39 +61,65 @@ pub const GREEN: Color = Color{r:0.,g:1.,b:0.,a:0.5};
 pub const RED: Color = Color{r:1.,g:0.,b:0.,a:0.5};
 /// A half transparent blue
 pub const BLUE: Color = Color{r:0.,g:0.,b:1.,a:0.5};
+/// A half transparent yellow
+pub const YELLOW: Color = Color{r:1.,g:1.,b:0.,a:0.5};
+
+/// Gets the value following a command line option like `--seed 42`
+fn arg_value(name: &str) -> Option<String> {
+    let mut args = std::env::args().skip_while(|s| s != name);
+    args.next();
+    args.next()
+}
+
+/// Parses the value following a command line option, quitting if it isn't valid
+fn parse_arg<T: std::str::FromStr>(name: &str) -> Option<T> {
+    arg_value(name).map(|s| match s.parse() {
+        Ok(value) => value,
+        Err(_) => {
+            println!("Invalid value `{}` for {}", s, name);
+            std::process::exit(1)
+        }
+    })
+}
 
 fn main() {
-    // Set window mode
-    // If `--fullscreen` is parsed, open in 1440p fullscreen (resolution of desktop, this feature is experimental)
-    let window_mode = if std::env::args().any(|s| s == "--fullscreen") {
-        conf::WindowMode::default()
-            .fullscreen_type(conf::FullscreenType::Desktop)
-            .dimensions(2560, 1440)
-    } else {
-        // Normal resolution of the game
-        conf::WindowMode::default().dimensions(1000, 750)
-    };
+    // Size of the window, `--width` and `--height` can be given to change it
+    let width = parse_arg("--width").unwrap_or(1000);
+    let height = parse_arg("--height").unwrap_or(750);
 
     // Create a context (the part that runs the game loop)
     let mut ctx = ContextBuilder::new("shooter", "LFalch")
-        .window_setup(conf::WindowSetup::default().title("Shooter"))
-        .window_mode(window_mode)
+        .window_setup(conf::WindowSetup::default().title("Shooter").resizable(true))
+        .window_mode(conf::WindowMode::default().dimensions(width, height))
         .build().unwrap();
 
     // Add the workspace directory to the filesystem when running with cargo
-    // This is only used in development
-    if let Ok(manifest_dir) = ::std::env::var("CARGO_MANIFEST_DIR") {
+    // This is only used in development, where the resources are also reloaded when they change
+    let dev_resources = ::std::env::var("CARGO_MANIFEST_DIR").ok().map(|manifest_dir| {
         let mut path = ::std::path::PathBuf::from(manifest_dir);
         path.push("resources");
         ctx.filesystem.mount(&path, true);
-    }
+        path
+    });
 
-    // Tries to create a game state and runs it if succesful
-    match State::new(&mut ctx) {
+    // Use the seed given with `--seed` or make one from the current time
+    let seed = parse_arg("--seed").unwrap_or_else(|| {
+        let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap();
+        now.as_secs() ^ now.subsec_nanos() as u64
+    });
+
+    // Tries to load the assets and runs the game from the main menu if succesful
+    match SceneStack::new(&mut ctx, seed, dev_resources) {
         Err(e) => {
             println!("Couldn't load game {}", e);
         }
         Ok(mut game) => {
+            // If `--fullscreen` is passed, go fullscreen at the resolution of the desktop
+            if std::env::args().any(|s| s == "--fullscreen") {
+                if let Err(e) = game.set_fullscreen(&mut ctx, true) {
+                    println!("Couldn't go fullscreen: {}", e);
+                }
+            }
             // Run the game loop
             match run(&mut ctx, &mut game) {
                 Ok(_) => println!("Clean exit"),
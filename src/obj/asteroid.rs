@@ -0,0 +1,145 @@
+use std::f32::consts::PI;
+use std::ops::{Deref, DerefMut};
+
+use ggez::graphics::{Point2, Color};
+use {Object, DestructableObj};
+use super::AsObject;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// How big an asteroid is
+pub enum Size {
+    /// Smaller than the ship
+    Small,
+    /// About the size of the ship
+    Medium,
+    /// Bigger than the ship
+    Large,
+}
+
+impl Size {
+    /// All the sizes
+    pub const ALL: [Size; 3] = [Size::Small, Size::Medium, Size::Large];
+    /// Radius of asteroids of this size
+    pub fn radius(&self) -> f32 {
+        match *self {
+            Size::Small => 14.,
+            Size::Medium => 24.,
+            Size::Large => 40.,
+        }
+    }
+    /// Hit points of a rock asteroid of this size
+    pub fn health(&self) -> f32 {
+        match *self {
+            Size::Small => 40.,
+            Size::Medium => 100.,
+            Size::Large => 200.,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// What an asteroid is made of
+pub enum Material {
+    /// Light and brittle
+    Ice,
+    /// The normal asteroid
+    Rock,
+    /// Heavy and tough
+    Metal,
+}
+
+impl Material {
+    /// All the materials
+    pub const ALL: [Material; 3] = [Material::Ice, Material::Rock, Material::Metal];
+    /// Mass per area
+    pub fn density(&self) -> f32 {
+        match *self {
+            Material::Ice => 0.4,
+            Material::Rock => 1.,
+            Material::Metal => 2.5,
+        }
+    }
+    /// What the hit points of the size are multiplied by
+    pub fn toughness(&self) -> f32 {
+        match *self {
+            Material::Ice => 0.6,
+            Material::Rock => 1.,
+            Material::Metal => 1.5,
+        }
+    }
+    /// The fraction of damage that is ignored
+    pub fn resistance(&self) -> f32 {
+        match *self {
+            Material::Ice => 0.,
+            Material::Rock => 0.2,
+            Material::Metal => 0.5,
+        }
+    }
+    /// The colour the asteroid sprite is tinted with
+    pub fn tint(&self) -> Color {
+        match *self {
+            Material::Ice => Color::new(0.7, 0.85, 1., 1.),
+            Material::Rock => Color::new(1., 1., 1., 1.),
+            Material::Metal => Color::new(1., 0.8, 0.6, 1.),
+        }
+    }
+}
+
+/// A `DestructableObj` with a size and a material
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Asteroid {
+    obj: DestructableObj,
+    /// The size it was made with
+    pub size: Size,
+    /// What it's made of
+    pub material: Material,
+}
+
+impl Asteroid {
+    /// Make a new asteroid of the given archetype
+    pub fn new(pos: Point2, size: Size, material: Material) -> Self {
+        let rad = size.radius();
+        let mut obj = DestructableObj::new(pos, rad, size.health() * material.toughness());
+        obj.mass = material.density() * PI * rad.powi(2);
+
+        Asteroid {
+            obj,
+            size,
+            material,
+        }
+    }
+    /// Take damage reduced by the resistance of the material
+    pub fn hit(&mut self, dmg: f32) {
+        let resistance = self.material.resistance();
+        self.obj.hit(dmg * (1. - resistance));
+    }
+}
+
+impl AsObject for Asteroid {
+    fn as_obj(&self) -> &Object {
+        &self.obj
+    }
+    fn as_obj_mut(&mut self) -> &mut Object {
+        &mut self.obj
+    }
+    fn color(&self) -> Option<Color> {
+        Some(self.material.tint())
+    }
+}
+
+impl Deref for Asteroid {
+    type Target = DestructableObj;
+    fn deref(&self) -> &Self::Target {
+        &self.obj
+    }
+}
+impl DerefMut for Asteroid {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.obj
+    }
+}
+
+/// Makes a medium rock asteroid
+pub fn make_asteroid(p: Point2) -> Asteroid {
+    Asteroid::new(p, Size::Medium, Material::Rock)
+}
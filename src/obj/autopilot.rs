@@ -0,0 +1,178 @@
+use std::f32::consts::PI;
+
+use ggez::graphics::{Point2, Vector2};
+use ggez::nalgebra as na;
+use {ThrustedObj, Controls, DDELTA, DELTA, angle_to_vec, angle_from_vec};
+
+/// Speeds below this are treated as standing still
+const STOP_SPEED: f32 = 0.5;
+/// How far off (in radians) the nose can be from the burn direction while the main engine is used
+const BURN_ANGLE: f32 = 0.25;
+/// Fastest speed used when approaching a point
+const MAX_APPROACH_SPEED: f32 = 300.;
+/// Distance to a point at which it's treated as reached
+const ARRIVE_DISTANCE: f32 = 5.;
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+/// Flight-assist modes that fly the ship
+pub enum Autopilot {
+    /// Come to a stop
+    KillVelocity,
+    /// Turn to and hold the given heading
+    HoldHeading(f32),
+    /// Move with the same velocity as the target
+    MatchVelocity,
+    /// Fly to the point and stop there
+    Approach(f32, f32),
+    /// Point the nose along the velocity
+    Prograde,
+    /// Point the nose against the velocity
+    Retrograde,
+}
+
+/// Wraps an angle difference into `-π...π`
+pub fn angle_diff(a: f32, b: f32) -> f32 {
+    let mut d = (a - b) % (2. * PI);
+    if d > PI {
+        d -= 2. * PI;
+    } else if d < -PI {
+        d += 2. * PI;
+    }
+    d
+}
+
+impl Autopilot {
+    /// Name shown in the HUD
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Autopilot::KillVelocity => "Kill velocity",
+            Autopilot::HoldHeading(_) => "Hold heading",
+            Autopilot::MatchVelocity => "Match target",
+            Autopilot::Approach(_, _) => "Approach",
+            Autopilot::Prograde => "Prograde",
+            Autopilot::Retrograde => "Retrograde",
+        }
+    }
+    /// Works out the control commands for this tick
+    ///
+    /// `target_vel` is the velocity of the targeted object, if any
+    pub fn controls(&self, ship: &ThrustedObj, target_vel: Option<Vector2>) -> Controls {
+        match *self {
+            Autopilot::KillVelocity => change_velocity(ship, -ship.vel),
+            Autopilot::HoldHeading(heading) => Controls {
+                turn: turn_towards(ship, heading),
+                .. Controls::default()
+            },
+            Autopilot::MatchVelocity => match target_vel {
+                Some(v) => change_velocity(ship, v - ship.vel),
+                None => Controls::default(),
+            },
+            Autopilot::Approach(x, y) => {
+                let to_point = Point2::new(x, y) - ship.pos;
+                let dist = to_point.norm();
+                if dist < ARRIVE_DISTANCE {
+                    change_velocity(ship, -ship.vel)
+                } else {
+                    // Go as fast as possible while still being able to stop in time
+                    let brake = 0.5 * max_deceleration(ship);
+                    let speed = (2. * brake * dist).sqrt().min(MAX_APPROACH_SPEED);
+                    change_velocity(ship, speed / dist * to_point - ship.vel)
+                }
+            }
+            Autopilot::Prograde | Autopilot::Retrograde => {
+                if ship.vel.norm() < STOP_SPEED {
+                    return Controls {
+                        turn: stop_turning(ship),
+                        .. Controls::default()
+                    };
+                }
+                let mut heading = angle_from_vec(&ship.vel);
+                if *self == Autopilot::Retrograde {
+                    heading += PI;
+                }
+                Controls {
+                    turn: turn_towards(ship, heading),
+                    .. Controls::default()
+                }
+            }
+        }
+    }
+}
+
+/// Largest angular acceleration the ship can make
+fn max_ang_acc(ship: &ThrustedObj) -> f32 {
+    ship.rcs.torque / ship.inertia()
+}
+
+/// Turn command that brings the angular velocity to zero
+fn stop_turning(ship: &ThrustedObj) -> f32 {
+    let max = max_ang_acc(ship);
+    (-ship.ang_vel / (max * DELTA)).max(-1.).min(1.)
+}
+
+/// Turn command for reaching a heading and stopping there
+fn turn_towards(ship: &ThrustedObj, heading: f32) -> f32 {
+    let max = max_ang_acc(ship);
+    let err = angle_diff(heading, ship.rot);
+    // The fastest we can turn and still stop in time at full torque
+    let wanted = err.signum() * (2. * max * err.abs()).sqrt();
+
+    ((wanted - ship.ang_vel) / (max * DELTA)).max(-1.).min(1.)
+}
+
+/// Velocity change of a tick of the main engine at the current throttle
+fn main_dv(ship: &ThrustedObj) -> f32 {
+    let usg = (ship.thruster.throttle_usage * DDELTA).min(ship.thruster.fuel);
+    ship.thruster.efficiency() * usg as f32 / ship.mass
+}
+
+/// Velocity change of a tick of RCS thrusters at full power
+fn rcs_dv(ship: &ThrustedObj, efficiency: f32) -> f32 {
+    let usg = (ship.rcs.usage * DDELTA).min(ship.thruster.fuel);
+    efficiency * usg as f32 / ship.mass
+}
+
+/// The deceleration the ship can count on when stopping
+fn max_deceleration(ship: &ThrustedObj) -> f32 {
+    let main = main_dv(ship);
+    let rcs = rcs_dv(ship, ship.rcs.reverse_efficiency);
+    main.max(rcs) / DELTA
+}
+
+/// Controls for changing the velocity by `dv`
+///
+/// Big changes are done by turning towards `dv` and using the main engine,
+/// what's left is trimmed with the RCS
+fn change_velocity(ship: &ThrustedObj, dv: Vector2) -> Controls {
+    let size = dv.norm();
+    if size < STOP_SPEED {
+        return Controls {
+            turn: stop_turning(ship),
+            .. Controls::default()
+        };
+    }
+    let dir = angle_to_vec(ship.rot);
+    let side = Vector2::new(-dir.y, dir.x);
+    let forward = na::dot(&dv, &dir);
+    let lateral = na::dot(&dv, &side);
+
+    let main = main_dv(ship);
+    let lateral_dv = rcs_dv(ship, ship.rcs.lateral_efficiency);
+    let reverse_dv = rcs_dv(ship, ship.rcs.reverse_efficiency);
+
+    // Only bother turning if the main engine would help
+    let use_main = main > 0. && size >= main;
+    let turn = if use_main {
+        turn_towards(ship, angle_from_vec(&dv))
+    } else {
+        stop_turning(ship)
+    };
+    let aligned = forward / size > BURN_ANGLE.cos();
+
+    Controls {
+        main: use_main && aligned && forward >= main,
+        strafe: if lateral_dv > 0. { (lateral / lateral_dv).max(-1.).min(1.) } else { 0. },
+        reverse: if forward < 0. && reverse_dv > 0. { (-forward / reverse_dv).min(1.) } else { 0. },
+        turn,
+    }
+}
@@ -1,15 +1,19 @@
 pub(super) mod phys;
+pub(super) mod asteroid;
+pub(super) mod pickup;
+pub(super) mod weapon;
+pub(super) mod autopilot;
 
 use std::f32::consts::PI;
 use std::ops::{Deref, DerefMut};
 
 use ggez::{Context, GameResult};
-use ggez::graphics::{self, Point2, Vector2, Image};
+use ggez::graphics::{self, Point2, Vector2, Image, Color, Rect};
 use ggez::nalgebra as na;
 
-use {GREEN, DELTA};
+use {GREEN, DELTA, Assets, Sprite};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// A simple object that can be drawn to the screen
 pub struct Object {
     #[serde(serialize_with = "::save::point_ser", deserialize_with = "::save::point_des")]
@@ -24,6 +28,11 @@ pub struct Object {
     pub vel: Vector2,
     /// The mass
     pub mass: f32,
+    #[serde(skip)]
+    /// Seconds its sprite has been animated for
+    ///
+    /// This isn't saved, so animations start over when a game is loaded
+    pub anim_time: f32,
 }
 
 impl Object {
@@ -35,14 +44,24 @@ impl Object {
             rot: 0.,
             vel: na::zero(),
             mass: rad.powi(2) * PI,
+            anim_time: 0.,
         }
     }
-    /// Draw the object
-    pub fn draw(&self, ctx: &mut Context, img: &Image) -> GameResult<()> {
+    /// Draw the object with the current frame of a sprite
+    pub fn draw(&self, ctx: &mut Context, assets: &Assets, s: Sprite) -> GameResult<()> {
+        let (img, info) = (assets.get_img(s), assets.info(s));
+        let src = info.src(img, info.frame_at(self.anim_time));
+        self.draw_ex(ctx, img, src, info.offset(), 1., None)
+    }
+    /// Draw the `src` part of an image scaled and with an optional tint, placed by the `offset` of the image
+    pub fn draw_ex(&self, ctx: &mut Context, img: &Image, src: Rect, offset: Point2, scale: f32, color: Option<Color>) -> GameResult<()> {
         let drawparams = graphics::DrawParam {
+            src,
             dest: self.pos,
             rotation: self.rot,
-            offset: Point2::new(0.5, 0.5),
+            offset,
+            scale: Point2::new(scale, scale),
+            color,
             .. Default::default()
         };
         graphics::draw_ex(ctx, img, drawparams)
@@ -76,9 +95,10 @@ impl Object {
         self.pos = center + diff;
         oth.pos = center - diff;
     }
-    /// Update its position and velocity using basic physics
+    /// Update its position and velocity using basic physics and advance its animation
     pub fn update(&mut self) {
         self.pos += self.vel * DELTA;
+        self.anim_time += DELTA;
     }
     /// Realistic elastic collision
     pub fn elastic_collide(&mut self, oth: &mut Self) -> (Vector2, Vector2) {
@@ -119,6 +139,11 @@ pub trait AsObject {
     fn draw_lines(&self, ctx: &mut Context) -> GameResult<()> {
         self.as_obj().draw_lines(ctx)
     }
+    #[inline]
+    /// Colour to tint the sprite with, if any
+    fn color(&self) -> Option<Color> {
+        None
+    }
 }
 
 impl AsObject for Object {
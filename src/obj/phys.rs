@@ -2,16 +2,18 @@ use std::ops::{Deref, DerefMut};
 
 use ggez::graphics::{self, Point2, Vector2};
 use ggez::{Context, GameResult};
-use {RED, Object, DDELTA, DELTA, Sprite, angle_to_vec};
+use {RED, Object, DDELTA, DELTA, Sprite, Autopilot, angle_to_vec};
 use super::AsObject;
 
 /// A `PhysObj` with health
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct DestructableObj {
     /// Inner `PhysObj`
     pub obj: Object,
     /// Hit points
     pub health: f32,
+    /// Whether this is the target of a mission objective
+    pub target: bool,
 }
 
 impl DestructableObj {
@@ -19,7 +21,8 @@ impl DestructableObj {
     pub fn new(pos: Point2, radius: f32, health: f32) -> Self {
         DestructableObj {
             obj: Object::new(pos, radius),
-            health
+            health,
+            target: false,
         }
     }
     /// Take damage
@@ -49,23 +52,72 @@ impl DerefMut for DestructableObj {
 }
 
 /// A self acceleratable `DestructableObj`
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ThrustedObj {
     obj: DestructableObj,
     #[serde(serialize_with = "::save::vec_ser", deserialize_with = "::save::vec_des")]
     acc: Vector2,
     /// Thruster of the object
     pub thruster: Thruster,
+    /// Mass without any fuel
+    pub dry_mass: f32,
+    /// Angular velocity in radians per second
+    pub ang_vel: f32,
+    /// Reaction control system used for strafing, reversing and turning
+    pub rcs: Rcs,
+    /// What the pilot is doing this tick
+    pub controls: Controls,
+    /// Flight-assist mode flying the object, if any
+    pub autopilot: Option<Autopilot>,
+}
+
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize)]
+/// Control commands for a `ThrustedObj`
+pub struct Controls {
+    /// Whether the main engine is on
+    pub main: bool,
+    /// Sideways thrust from `-1` (left) to `1` (right)
+    pub strafe: f32,
+    /// Reverse thrust from `0` to `1`
+    pub reverse: f32,
+    /// Torque from `-1` (anticlockwise) to `1` (clockwise)
+    pub turn: f32,
 }
 
 impl ThrustedObj {
     /// Creats a new `ThrustedObj`
-    pub fn new(pos: Point2, radius: f32, health: f32, thruster: Thruster) -> Self {
-        ThrustedObj {
-            obj: DestructableObj::new(pos, radius, health),
+    ///
+    /// The dry mass is the mass of an `Object` with the radius
+    pub fn new(pos: Point2, radius: f32, health: f32, thruster: Thruster, rcs: Rcs) -> Self {
+        let obj = DestructableObj::new(pos, radius, health);
+        let dry_mass = obj.mass;
+        let mut thrusted = ThrustedObj {
+            obj,
             acc: Vector2::new(0., 0.),
             thruster,
-        }
+            dry_mass,
+            ang_vel: 0.,
+            rcs,
+            controls: Controls::default(),
+            autopilot: None,
+        };
+        thrusted.update_mass();
+        thrusted
+    }
+    /// Sets the mass to the dry mass plus the mass of the fuel
+    ///
+    /// Has to be called whenever the fuel changes
+    pub fn update_mass(&mut self) {
+        self.obj.mass = self.dry_mass + self.thruster.fuel_mass();
+    }
+    /// The change in velocity the remaining fuel can give (Tsiolkovsky rocket equation)
+    pub fn delta_v(&self) -> f32 {
+        let wet_mass = self.dry_mass + self.thruster.fuel_mass();
+        self.thruster.exhaust_velocity() * (wet_mass / self.dry_mass).ln()
+    }
+    /// Moment of inertia, treating the object as a uniform disc
+    pub fn inertia(&self) -> f32 {
+        0.5 * self.mass * self.rad.powi(2)
     }
 }
 
@@ -77,13 +129,35 @@ impl AsObject for ThrustedObj {
         &mut self.obj
     }
     fn update(&mut self) {
+        let controls = self.controls;
+        // Forwards and to the right of the object
+        let dir = angle_to_vec(self.rot);
+        let side = Vector2::new(-dir.y, dir.x);
+
+        // The impulse is divided by the mass before the fuel was burnt
+        let mass = self.mass;
+        let mut impulse = Vector2::new(0., 0.);
+
+        self.thruster.power = controls.main;
         if self.thruster.power {
-            self.acc = self.thruster.burn() * angle_to_vec(self.rot);
-        } else {
-            self.acc = Vector2::new(0., 0.);
+            impulse += self.thruster.burn() * dir;
         }
+        let lateral_efficiency = self.rcs.lateral_efficiency;
+        let reverse_efficiency = self.rcs.reverse_efficiency;
+        impulse += controls.strafe.signum() * self.rcs.burn(&mut self.thruster.fuel, controls.strafe.abs(), lateral_efficiency) * side;
+        impulse -= self.rcs.burn(&mut self.thruster.fuel, controls.reverse, reverse_efficiency) * dir;
+
+        self.acc = impulse / mass;
+        self.update_mass();
+
+        // Turning is done with reaction wheels, so it doesn't use fuel
+        let ang_acc = controls.turn.max(-1.).min(1.) * self.rcs.torque / self.inertia();
+        self.rot += 0.5 * ang_acc * DELTA * DELTA + self.ang_vel * DELTA;
+        self.ang_vel += ang_acc * DELTA;
+
         self.pos += 0.5 * self.acc * DELTA + self.vel * DELTA;
         self.vel += self.acc;
+        self.anim_time += DELTA;
     }
     fn draw_lines(&self, ctx: &mut Context) -> GameResult<()> {
         self.obj.draw_lines(ctx)?;
@@ -107,7 +181,7 @@ impl DerefMut for ThrustedObj {
 }
 
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// The engine
 pub struct Thruster {
     /// The current fuel
@@ -116,10 +190,16 @@ pub struct Thruster {
     pub throttle_usage: f64,
     /// Whether it is turned on or off
     pub power: bool,
+    /// Impulse per litre of fuel
     efficiency: f32,
     max_throttle: f64,
 }
 
+/// Mass of a litre of fuel
+pub const FUEL_DENSITY: f32 = 0.5;
+/// Fuel usages where the flame of the ship's sprite gets bigger
+pub const THROTTLE_STEPS: [f64; 3] = [4.5, 9., 22.5];
+
 impl Thruster {
     /// New `Thruster`
     pub fn new(fuel: f64, efficiency: f32, max_throttle: f64) -> Self {
@@ -131,7 +211,7 @@ impl Thruster {
             power: false,
         }
     }
-    /// Burn fuel and return the acceleration provided
+    /// Burn fuel and return the impulse provided
     pub fn burn(&mut self) -> f32 {
         let mut usg = self.throttle_usage * DDELTA;
         if usg > self.fuel {
@@ -142,6 +222,22 @@ impl Thruster {
 
         self.efficiency * usg as f32
     }
+    /// Impulse per litre of fuel
+    pub fn efficiency(&self) -> f32 {
+        self.efficiency
+    }
+    /// The mass of the fuel left
+    pub fn fuel_mass(&self) -> f32 {
+        self.fuel as f32 * FUEL_DENSITY
+    }
+    /// Impulse per mass of fuel burnt
+    pub fn exhaust_velocity(&self) -> f32 {
+        self.efficiency / FUEL_DENSITY
+    }
+    /// The highest fuel usage the throttle can be set to
+    pub fn max_throttle(&self) -> f64 {
+        self.max_throttle
+    }
     /// Changes throttle by adding the amount and making sure its in bounds
     pub fn throttle(&mut self, throttle: f64) {
         self.throttle_usage += throttle;
@@ -155,11 +251,11 @@ impl Thruster {
     pub fn sprite(&self) -> Sprite {
         if !self.power || self.throttle_usage <= 0. {
              Sprite::ShipOff
-        } else if self.throttle_usage <= 4.5 {
+        } else if self.throttle_usage <= THROTTLE_STEPS[0] {
             Sprite::ShipOn
-        } else if self.throttle_usage <= 9. {
+        } else if self.throttle_usage <= THROTTLE_STEPS[1] {
             Sprite::ShipLit
-        } else if self.throttle_usage <= 22.5 {
+        } else if self.throttle_usage <= THROTTLE_STEPS[2] {
             Sprite::ShipSpeed2
         } else {
             Sprite::ShipSpeed3
@@ -167,27 +263,94 @@ impl Thruster {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Small thrusters for moving sideways and backwards, and for turning
+pub struct Rcs {
+    /// Fuel used per second by a set of thrusters at full power
+    pub usage: f64,
+    /// Impulse per litre of fuel of the sideways thrusters
+    pub lateral_efficiency: f32,
+    /// Impulse per litre of fuel of the reverse thrusters
+    pub reverse_efficiency: f32,
+    /// Torque of the reaction wheels at full power
+    pub torque: f32,
+}
+
+impl Rcs {
+    /// Burns fuel from the tank at the given power (`0...1`) and returns the impulse
+    pub fn burn(&self, fuel: &mut f64, power: f32, efficiency: f32) -> f32 {
+        if power <= 0. {
+            return 0.
+        }
+        let mut usg = self.usage * power.min(1.) as f64 * DDELTA;
+        if usg > *fuel {
+            usg = *fuel;
+        }
+        *fuel -= usg;
+
+        efficiency * usg as f32
+    }
+}
+
+/// Litres of fuel in a full tank of the player
+pub const PLAYER_FUEL: f64 = 2e3;
+
 const PLAYER_ENGINE: Thruster = Thruster {
-    fuel: 2e3,
+    fuel: PLAYER_FUEL,
     throttle_usage: 0.,
     power: false,
-    efficiency: 7.3,
+    // Gives about the same acceleration as before fuel had mass with half a tank
+    efficiency: 12800.,
     max_throttle: 45.,
 };
 
+const PLAYER_RCS: Rcs = Rcs {
+    usage: 6.,
+    lateral_efficiency: 8000.,
+    reverse_efficiency: 10000.,
+    torque: 1.8e6,
+};
+
+/// Hit points of the player when fully repaired
+pub const PLAYER_HEALTH: f32 = 40.;
+/// Radius of the player's ship
+pub const PLAYER_RADIUS: f32 = 20.;
+/// Radius of bullets
+pub const BULLET_RADIUS: f32 = 7.5;
+
 /// Make a player
 pub fn make_player(p: Point2) -> ThrustedObj {
-    ThrustedObj::new(p, Sprite::ShipOff.radius(), 40., PLAYER_ENGINE)
+    ThrustedObj::new(p, PLAYER_RADIUS, PLAYER_HEALTH, PLAYER_ENGINE, PLAYER_RCS)
 }
 /// Makes a `PhysObj` with the size of bullet
 pub fn make_bullet(p: Point2) -> Object {
-    Object::new(p, Sprite::Bullet.radius())
-}
-/// Makes a `PhysObj` with the size of fuel
-pub fn make_fuel(p: Point2) -> Object {
-    Object::new(p, Sprite::Fuel.radius())
+    Object::new(p, BULLET_RADIUS)
 }
-/// Makes a `DestructableObj` with the size of asteroid
-pub fn make_asteroid(p: Point2) -> DestructableObj {
-    DestructableObj::new(p, Sprite::Asteroid.radius(), 100.)
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {SpriteInfo, Shape, LoopMode};
+
+    #[test]
+    fn ship_animation_advances() {
+        let info = SpriteInfo {
+            id: "ship_on".to_owned(),
+            path: "/ship_on.png".to_owned(),
+            width: 40.,
+            height: 40.,
+            pivot: [0.5, 0.5],
+            shape: Shape::Circle,
+            radius: 20.,
+            frames: vec![[0., 0., 40., 40.], [40., 0., 40., 40.]],
+            frame_time: 0.1,
+            loop_mode: LoopMode::Loop,
+        };
+        let mut ship = make_player(Point2::new(0., 0.));
+        assert_eq!(info.frame_at(ship.anim_time), 0);
+        for _ in 0..(0.15 / DELTA).ceil() as usize {
+            AsObject::update(&mut ship);
+        }
+        assert_eq!(info.frame_at(ship.anim_time), 1);
+    }
 }
@@ -0,0 +1,99 @@
+use ggez::graphics::{Point2, Color};
+use {Object, Weapon};
+use super::AsObject;
+
+/// Radius of pickups
+pub const PICKUP_RADIUS: f32 = 16.;
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+/// What a pickup gives when it's collected
+pub enum PickupKind {
+    /// Litres of fuel
+    Fuel,
+    /// Hit points repaired
+    Repair,
+    /// Bullets
+    Ammo,
+    /// Points added to the score
+    Score,
+    /// Unlocks a weapon
+    Weapon(Weapon),
+}
+
+impl PickupKind {
+    /// How the pickup has to be touched to be collected if nothing else is specified
+    pub fn default_capture(&self) -> Capture {
+        match *self {
+            PickupKind::Fuel | PickupKind::Repair | PickupKind::Weapon(_) => Capture::MaxSpeed(30.),
+            PickupKind::Ammo | PickupKind::Score => Capture::Touch,
+        }
+    }
+    /// The colour the pickup sprite is tinted with
+    pub fn tint(&self) -> Color {
+        match *self {
+            PickupKind::Fuel => Color::new(1., 1., 1., 1.),
+            PickupKind::Repair => Color::new(0.5, 1., 0.5, 1.),
+            PickupKind::Ammo => Color::new(1., 0.8, 0.4, 1.),
+            PickupKind::Score => Color::new(1., 1., 0.3, 1.),
+            PickupKind::Weapon(_) => Color::new(1., 0.4, 1., 1.),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+/// Conditions for collecting a pickup when it's touched
+pub enum Capture {
+    /// Always collected on touch
+    Touch,
+    /// Only collected if the relative speed is at most the given value, otherwise it bounces off
+    MaxSpeed(f32),
+}
+
+/// An object that gives something when the player collects it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pickup {
+    /// Inner `Object`
+    pub obj: Object,
+    /// What it gives
+    pub kind: PickupKind,
+    /// How much it gives
+    pub amount: f32,
+    /// When it can be collected
+    pub capture: Capture,
+}
+
+impl Pickup {
+    /// Make a new pickup with the default capture conditions of its kind
+    pub fn new(pos: Point2, kind: PickupKind, amount: f32) -> Self {
+        Pickup {
+            obj: Object::new(pos, PICKUP_RADIUS),
+            kind,
+            amount,
+            capture: kind.default_capture(),
+        }
+    }
+    /// Checks whether the touching object can collect it
+    pub fn can_capture(&self, collector: &Object) -> bool {
+        match self.capture {
+            Capture::Touch => true,
+            Capture::MaxSpeed(max) => (self.obj.vel - collector.vel).norm() <= max,
+        }
+    }
+}
+
+impl AsObject for Pickup {
+    fn as_obj(&self) -> &Object {
+        &self.obj
+    }
+    fn as_obj_mut(&mut self) -> &mut Object {
+        &mut self.obj
+    }
+    fn color(&self) -> Option<Color> {
+        Some(self.kind.tint())
+    }
+}
+
+/// Makes a fuel pickup with 200 L
+pub fn make_fuel(p: Point2) -> Pickup {
+    Pickup::new(p, PickupKind::Fuel, 200.)
+}
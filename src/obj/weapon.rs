@@ -0,0 +1,85 @@
+use {Object, angle_to_vec, make_bullet};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// The kinds of guns the ship can have
+pub enum Weapon {
+    /// Fires a single bullet forwards
+    Cannon,
+    /// Fires three bullets in a fan
+    Spread,
+}
+
+impl Weapon {
+    /// Name shown in the HUD
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Weapon::Cannon => "Cannon",
+            Weapon::Spread => "Spread",
+        }
+    }
+    /// The directions, relative to the ship, bullets are fired in
+    fn angles(&self) -> &'static [f32] {
+        match *self {
+            Weapon::Cannon => &[0.],
+            Weapon::Spread => &[-0.15, 0., 0.15],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// The weapons and ammunition of the ship
+pub struct Loadout {
+    /// Bullets left
+    pub ammo: u32,
+    /// The weapons that can be selected
+    pub weapons: Vec<Weapon>,
+    selected: usize,
+}
+
+impl Default for Loadout {
+    fn default() -> Self {
+        Loadout {
+            ammo: 100,
+            weapons: vec![Weapon::Cannon],
+            selected: 0,
+        }
+    }
+}
+
+impl Loadout {
+    /// The weapon being used
+    pub fn weapon(&self) -> Weapon {
+        self.weapons[self.selected]
+    }
+    /// Selects the next unlocked weapon
+    pub fn cycle(&mut self) {
+        self.selected = (self.selected + 1) % self.weapons.len();
+    }
+    /// Unlocks a weapon if it wasn't already
+    pub fn unlock(&mut self, weapon: Weapon) {
+        if !self.weapons.contains(&weapon) {
+            self.weapons.push(weapon);
+        }
+    }
+    /// Fires the selected weapon from a ship, using up ammo
+    ///
+    /// Returns the new bullets
+    pub fn fire(&mut self, ship: &Object) -> Vec<Object> {
+        let mut bullets = Vec::new();
+        for &angle in self.weapon().angles() {
+            if self.ammo == 0 {
+                break
+            }
+            self.ammo -= 1;
+
+            let rot = ship.rot + angle;
+            let d = angle_to_vec(rot);
+            let mut bullet = make_bullet(ship.pos + 34. * d);
+            bullet.vel = ship.vel + 200. * d;
+            bullet.rot = rot;
+            bullets.push(bullet);
+        }
+        bullets
+    }
+}
+
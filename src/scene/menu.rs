@@ -0,0 +1,260 @@
+use ::*;
+use super::{Scene, Shared, Transition, skip_ticks};
+
+/// Colour the screen is dimmed with behind overlays
+const DIM: Color = Color{r:0.,g:0.,b:0.,a:0.6};
+/// Space between the title and the items of a menu
+const TITLE_SPACE: f32 = 24.;
+
+/// Makes a UI for a menu
+fn menu_ui(ctx: &Context, shared: &Shared) -> Ui {
+    let (width, height) = graphics::get_size(ctx);
+    Ui::new(shared.assets.clone(), width, height)
+}
+
+/// Darkens the whole screen
+fn dim_screen(ctx: &mut Context) -> GameResult<()> {
+    let (width, height) = graphics::get_size(ctx);
+    graphics::set_color(ctx, DIM)?;
+    graphics::rectangle(ctx, graphics::DrawMode::Fill, graphics::Rect::new(0., 0., width as f32, height as f32))
+}
+
+/// Draws the stars behind a menu
+fn draw_stars(ctx: &mut Context, shared: &Shared) -> GameResult<()> {
+    graphics::set_color(ctx, graphics::WHITE)?;
+    graphics::draw(ctx, shared.assets.get_img(Sprite::StarsBg), Point2::new(0., 0.), 0.)
+}
+
+/// The first screen
+pub struct MainMenu {
+    ui: Ui,
+    /// Shown if starting a game failed
+    error: Option<String>,
+}
+
+impl MainMenu {
+    /// Makes the main menu
+    pub fn new(ctx: &mut Context, shared: &Shared) -> GameResult<Self> {
+        Ok(MainMenu {
+            ui: menu_ui(ctx, shared),
+            error: None,
+        })
+    }
+    /// Starts a game, loading the save file if `load` is set
+    fn start(&mut self, ctx: &mut Context, shared: &Shared, load: bool) -> Transition {
+        let game = Game::new(ctx, shared.assets.clone(), shared.seed)
+            .and_then(|mut game| if load { game.load(SAVE_FILE).map(|_| game) } else { Ok(game) });
+        match game {
+            Ok(game) => Transition::Reset(Box::new(game)),
+            Err(e) => {
+                self.error = Some(format!("Couldn't start game: {}", e));
+                Transition::None
+            }
+        }
+    }
+}
+
+impl Scene for MainMenu {
+    fn update(&mut self, ctx: &mut Context, shared: &mut Shared) -> GameResult<Transition> {
+        skip_ticks(ctx);
+        let mut transition = Transition::None;
+
+        self.ui.begin();
+        self.ui.begin_panel(Anchor::Center, 0.);
+        self.ui.label(ctx, "SHOOTER")?;
+        self.ui.space(TITLE_SPACE);
+        if self.ui.button(ctx, "New game")? {
+            transition = self.start(ctx, shared, false);
+        }
+        if self.ui.button(ctx, "Load game")? {
+            transition = self.start(ctx, shared, true);
+        }
+        if self.ui.button(ctx, "Settings")? {
+            transition = Transition::Push(Box::new(SettingsMenu::new(ctx, shared)?));
+        }
+        if self.ui.button(ctx, "Quit")? {
+            transition = Transition::Push(Box::new(ConfirmQuit::new(ctx, shared)?));
+        }
+        if let Some(ref error) = self.error {
+            self.ui.begin_panel(Anchor::BottomLeft, 10.);
+            self.ui.label_colored(ctx, error, RED)?;
+        }
+        self.ui.end();
+
+        Ok(transition)
+    }
+    fn draw(&mut self, ctx: &mut Context, shared: &Shared) -> GameResult<()> {
+        draw_stars(ctx, shared)?;
+        self.ui.draw(ctx)
+    }
+    fn ui(&mut self) -> Option<&mut Ui> {
+        Some(&mut self.ui)
+    }
+}
+
+/// Shown on top of the game while it's paused
+pub struct PauseMenu {
+    ui: Ui,
+}
+
+impl PauseMenu {
+    /// Makes the pause menu
+    pub fn new(ctx: &mut Context, shared: &Shared) -> GameResult<Self> {
+        Ok(PauseMenu {
+            ui: menu_ui(ctx, shared),
+        })
+    }
+}
+
+impl Scene for PauseMenu {
+    fn update(&mut self, ctx: &mut Context, shared: &mut Shared) -> GameResult<Transition> {
+        skip_ticks(ctx);
+        let mut transition = Transition::None;
+
+        self.ui.begin();
+        self.ui.begin_panel(Anchor::Center, 0.);
+        self.ui.label(ctx, "PAUSED")?;
+        self.ui.space(TITLE_SPACE);
+        if self.ui.button(ctx, "Resume")? {
+            transition = Transition::Pop;
+        }
+        if self.ui.button(ctx, "Settings")? {
+            transition = Transition::Push(Box::new(SettingsMenu::new(ctx, shared)?));
+        }
+        if self.ui.button(ctx, "Main menu")? {
+            transition = Transition::Reset(Box::new(MainMenu::new(ctx, shared)?));
+        }
+        if self.ui.button(ctx, "Quit")? {
+            transition = Transition::Push(Box::new(ConfirmQuit::new(ctx, shared)?));
+        }
+        self.ui.end();
+
+        Ok(transition)
+    }
+    fn draw(&mut self, ctx: &mut Context, _shared: &Shared) -> GameResult<()> {
+        dim_screen(ctx)?;
+        self.ui.draw(ctx)
+    }
+    fn is_overlay(&self) -> bool {
+        true
+    }
+    fn ui(&mut self) -> Option<&mut Ui> {
+        Some(&mut self.ui)
+    }
+    fn key_down(&mut self, _ctx: &mut Context, _shared: &mut Shared, keycode: Keycode) -> Transition {
+        match keycode {
+            Keycode::Escape => Transition::Pop,
+            _ => Transition::None,
+        }
+    }
+}
+
+/// Changes the settings
+pub struct SettingsMenu {
+    ui: Ui,
+}
+
+impl SettingsMenu {
+    /// Makes the settings screen
+    pub fn new(ctx: &mut Context, shared: &Shared) -> GameResult<Self> {
+        Ok(SettingsMenu {
+            ui: menu_ui(ctx, shared),
+        })
+    }
+}
+
+impl Scene for SettingsMenu {
+    fn update(&mut self, ctx: &mut Context, shared: &mut Shared) -> GameResult<Transition> {
+        skip_ticks(ctx);
+        let mut transition = Transition::None;
+        let s = &mut shared.settings;
+
+        self.ui.begin();
+        self.ui.begin_panel(Anchor::Center, 0.);
+        self.ui.label(ctx, "SETTINGS")?;
+        self.ui.space(TITLE_SPACE);
+        self.ui.checkbox(ctx, "Radar", &mut s.show_radar)?;
+        self.ui.checkbox(ctx, "Rotate view with ship", &mut s.rotate_with_ship)?;
+        self.ui.checkbox(ctx, "Debug overlay", &mut s.debug_overlay)?;
+        self.ui.checkbox(ctx, "Compact HUD", &mut s.compact_hud)?;
+        if self.ui.checkbox(ctx, "Fullscreen", &mut s.fullscreen)? {
+            if let Err(e) = apply_fullscreen(ctx, s.fullscreen, shared.window_size) {
+                println!("Couldn't change fullscreen: {}", e);
+            }
+        }
+        self.ui.space(TITLE_SPACE);
+        if self.ui.button(ctx, "Back")? {
+            transition = Transition::Pop;
+        }
+        self.ui.end();
+
+        Ok(transition)
+    }
+    fn draw(&mut self, ctx: &mut Context, shared: &Shared) -> GameResult<()> {
+        draw_stars(ctx, shared)?;
+        dim_screen(ctx)?;
+        self.ui.draw(ctx)
+    }
+    fn ui(&mut self) -> Option<&mut Ui> {
+        Some(&mut self.ui)
+    }
+    fn key_down(&mut self, _ctx: &mut Context, _shared: &mut Shared, keycode: Keycode) -> Transition {
+        match keycode {
+            Keycode::Escape => Transition::Pop,
+            _ => Transition::None,
+        }
+    }
+}
+
+/// Asks whether to really quit
+pub struct ConfirmQuit {
+    ui: Ui,
+}
+
+impl ConfirmQuit {
+    /// Makes the dialog
+    pub fn new(ctx: &mut Context, shared: &Shared) -> GameResult<Self> {
+        Ok(ConfirmQuit {
+            ui: menu_ui(ctx, shared),
+        })
+    }
+}
+
+impl Scene for ConfirmQuit {
+    fn update(&mut self, ctx: &mut Context, _shared: &mut Shared) -> GameResult<Transition> {
+        skip_ticks(ctx);
+        let mut transition = Transition::None;
+
+        self.ui.begin();
+        self.ui.begin_panel(Anchor::Center, 0.);
+        self.ui.label(ctx, "Really quit?")?;
+        self.ui.space(TITLE_SPACE);
+        if self.ui.button(ctx, "No")? {
+            transition = Transition::Pop;
+        }
+        if self.ui.button(ctx, "Yes")? {
+            transition = Transition::Quit;
+        }
+        self.ui.end();
+
+        Ok(transition)
+    }
+    fn draw(&mut self, ctx: &mut Context, _shared: &Shared) -> GameResult<()> {
+        dim_screen(ctx)?;
+        self.ui.draw(ctx)
+    }
+    fn is_overlay(&self) -> bool {
+        true
+    }
+    fn ui(&mut self) -> Option<&mut Ui> {
+        Some(&mut self.ui)
+    }
+    fn key_down(&mut self, _ctx: &mut Context, _shared: &mut Shared, keycode: Keycode) -> Transition {
+        use Keycode::*;
+        match keycode {
+            Escape | N => Transition::Pop,
+            Y => Transition::Quit,
+            _ => Transition::None,
+        }
+    }
+}
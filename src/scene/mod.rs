@@ -0,0 +1,415 @@
+/// The menus shown around the game
+pub mod menu;
+
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use ::*;
+use self::menu::MainMenu;
+
+#[derive(Debug, Clone)]
+/// Options chosen in the settings screen
+pub struct Settings {
+    /// Whether the radar is shown
+    pub show_radar: bool,
+    /// Whether the view turns so the ship always points up
+    pub rotate_with_ship: bool,
+    /// Whether the debug overlay is shown
+    pub debug_overlay: bool,
+    /// Whether the HUD only shows the bars
+    pub compact_hud: bool,
+    /// Whether the window is fullscreen
+    pub fullscreen: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            show_radar: true,
+            rotate_with_ship: false,
+            debug_overlay: false,
+            compact_hud: false,
+            fullscreen: false,
+        }
+    }
+}
+
+/// Things all the scenes use
+pub struct Shared {
+    /// The images and font
+    pub assets: Rc<Assets>,
+    /// The current settings
+    pub settings: Settings,
+    /// Seed of the asteroid field of new games
+    pub seed: u64,
+    /// Size of the window when it isn't fullscreen
+    pub window_size: (u32, u32),
+}
+
+/// What the scene stack should do after an event
+pub enum Transition {
+    /// Nothing
+    None,
+    /// Put a new scene on top
+    Push(Box<Scene>),
+    /// Remove the top scene
+    Pop,
+    /// Replace the top scene
+    Replace(Box<Scene>),
+    /// Remove all the scenes and start over with this one
+    Reset(Box<Scene>),
+    /// Close the game
+    Quit,
+}
+
+/// A screen of the game, like a menu or the gameplay itself
+///
+/// Only the top scene gets updated and gets events
+pub trait Scene {
+    /// Update the logic
+    ///
+    /// By default the physics ticks are skipped, so the game doesn't catch up on them when it's resumed
+    fn update(&mut self, ctx: &mut Context, _shared: &mut Shared) -> GameResult<Transition> {
+        skip_ticks(ctx);
+        Ok(Transition::None)
+    }
+    /// Draw the scene
+    fn draw(&mut self, ctx: &mut Context, shared: &Shared) -> GameResult<()>;
+    /// Whether the scene below should be drawn first
+    fn is_overlay(&self) -> bool {
+        false
+    }
+    /// The UI that should get the input events before the scene does
+    fn ui(&mut self) -> Option<&mut Ui> {
+        None
+    }
+    /// Handle key down events
+    fn key_down(&mut self, _ctx: &mut Context, _shared: &mut Shared, _keycode: Keycode) -> Transition {
+        Transition::None
+    }
+    /// Handle key release events
+    fn key_up(&mut self, _ctx: &mut Context, _shared: &mut Shared, _keycode: Keycode) -> Transition {
+        Transition::None
+    }
+    /// Handle mouse down events
+    fn mouse_down(&mut self, _ctx: &mut Context, _shared: &mut Shared, _btn: MouseButton, _p: Point2) -> Transition {
+        Transition::None
+    }
+    /// Handle mouse release events
+    fn mouse_up(&mut self, _ctx: &mut Context, _shared: &mut Shared, _btn: MouseButton, _p: Point2) -> Transition {
+        Transition::None
+    }
+    /// Handle mouse movement
+    fn mouse_motion(&mut self, _ctx: &mut Context, _shared: &mut Shared, _p: Point2) {}
+    /// Handle the mouse wheel
+    fn mouse_wheel(&mut self, _ctx: &mut Context, _shared: &mut Shared, _y: i32) {}
+    /// Called when the game is closing or the scene is removed from the stack
+    fn on_quit(&mut self, _ctx: &mut Context) {}
+    /// Handle the screen changing size
+    ///
+    /// By default only the UI is told
+    fn resize(&mut self, _ctx: &mut Context, width: u32, height: u32) {
+        if let Some(ui) = self.ui() {
+            ui.resize(width, height);
+        }
+    }
+    /// Switch to newly loaded assets after the resources changed
+    ///
+    /// By default only the UI gets them
+    fn reload(&mut self, _ctx: &mut Context, assets: &Rc<Assets>) -> GameResult<()> {
+        if let Some(ui) = self.ui() {
+            ui.set_assets(assets.clone());
+        }
+        Ok(())
+    }
+}
+
+/// Switches between a window of `window_size` and fullscreen at the resolution of the desktop
+pub fn apply_fullscreen(ctx: &mut Context, fullscreen: bool, window_size: (u32, u32)) -> GameResult<()> {
+    // Desktop fullscreen always uses the resolution of the desktop, so the size is only used for the window
+    let fullscreen_type = if fullscreen { conf::FullscreenType::Desktop } else { conf::FullscreenType::Off };
+    let mode = ctx.conf.window_mode.fullscreen_type(fullscreen_type).dimensions(window_size.0, window_size.1);
+    graphics::set_mode(ctx, mode)
+}
+
+/// Skips the physics ticks that are due
+pub fn skip_ticks(ctx: &mut Context) {
+    while timer::check_update_time(ctx, DESIRED_FPS) {}
+}
+
+/// Seconds a successful reload or screenshot is reported for
+const STATUS_TIME: u64 = 3;
+
+/// The scenes of the game on top of each other
+pub struct SceneStack {
+    scenes: Vec<Box<Scene>>,
+    shared: Shared,
+    /// Watches the resources in development
+    watcher: Option<Watcher>,
+    /// Shows how reloading or taking a screenshot went
+    ui: Ui,
+    /// What happened at the last reload or screenshot, whether it failed and when
+    status: Option<(String, bool, Instant)>,
+    /// Whether the next frame should be saved as a screenshot
+    screenshot: bool,
+    /// The size the scenes were last told the screen has
+    size: (u32, u32),
+}
+
+impl SceneStack {
+    /// Loads the assets and starts at the main menu
+    ///
+    /// If `watch` is given, the assets are reloaded when the files in it change
+    pub fn new(ctx: &mut Context, seed: u64, watch: Option<PathBuf>) -> GameResult<Self> {
+        // Background colour is black
+        graphics::set_background_color(ctx, (0, 0, 0, 255).into());
+        let (width, height) = graphics::get_size(ctx);
+        let shared = Shared {
+            assets: Rc::new(Assets::new(ctx)?),
+            settings: Settings::default(),
+            seed,
+            window_size: (width, height),
+        };
+        let main_menu = MainMenu::new(ctx, &shared)?;
+        Ok(SceneStack {
+            scenes: vec![Box::new(main_menu)],
+            ui: Ui::new(shared.assets.clone(), width, height),
+            shared,
+            watcher: watch.map(Watcher::new),
+            status: None,
+            screenshot: false,
+            size: (width, height),
+        })
+    }
+    /// Goes fullscreen at the resolution of the desktop or back to a window
+    pub fn set_fullscreen(&mut self, ctx: &mut Context, fullscreen: bool) -> GameResult<()> {
+        self.shared.settings.fullscreen = fullscreen;
+        apply_fullscreen(ctx, fullscreen, self.shared.window_size)?;
+        self.resize(ctx);
+        Ok(())
+    }
+    /// Makes everything fit the current size of the window, if it has changed
+    fn resize(&mut self, ctx: &mut Context) {
+        let (width, height) = graphics::get_size(ctx);
+        if (width, height) == self.size {
+            return
+        }
+        self.size = (width, height);
+        if !self.shared.settings.fullscreen {
+            self.shared.window_size = (width, height);
+        }
+
+        // Draw with one unit per pixel instead of stretching
+        if let Err(e) = graphics::set_screen_coordinates(ctx, graphics::Rect::new(0., 0., width as f32, height as f32)) {
+            println!("Couldn't resize the screen to {}x{}: {}", width, height, e);
+        }
+        self.ui.resize(width, height);
+        for scene in &mut self.scenes {
+            scene.resize(ctx, width, height);
+        }
+    }
+    /// Loads the assets again and gives them to all the scenes
+    ///
+    /// If anything fails the old assets are kept where they can be
+    fn reload(&mut self, ctx: &mut Context, changed: &[String]) {
+        let result = Assets::new(ctx).map(Rc::new).and_then(|assets| {
+            self.shared.assets = assets.clone();
+            self.ui.set_assets(assets.clone());
+            for scene in &mut self.scenes {
+                scene.reload(ctx, &assets)?;
+            }
+            Ok(assets)
+        });
+        let status = match result {
+            Ok(ref assets) if !assets.errors().is_empty() => (assets.errors().join("\n"), true),
+            Ok(_) => (format!("Reloaded {}", changed.join(", ")), false),
+            Err(e) => (format!("Couldn't reload: {}", e), true),
+        };
+        self.set_status(status.0, status.1);
+    }
+    /// Prints a message and shows it in the corner of the screen
+    fn set_status(&mut self, msg: String, failed: bool) {
+        println!("{}", msg);
+        self.status = Some((msg, failed, Instant::now()));
+    }
+    /// Saves the next frame as a screenshot in the screenshot directory
+    pub fn take_screenshot(&mut self) {
+        self.screenshot = true;
+    }
+    /// Draws how the last reload or screenshot went
+    ///
+    /// Errors are shown until the next one
+    fn draw_status(&mut self, ctx: &mut Context) -> GameResult<()> {
+        self.ui.begin();
+        if let Some((ref msg, failed, time)) = self.status {
+            if failed || time.elapsed() < Duration::from_secs(STATUS_TIME) {
+                self.ui.begin_panel(Anchor::BottomLeft, 10.);
+                for line in msg.lines() {
+                    self.ui.label_colored(ctx, line, if failed { RED } else { GREEN })?;
+                }
+            }
+        }
+        self.ui.end();
+        self.ui.draw(ctx)
+    }
+    /// Removes the top scene after letting it clean up like the game was closing
+    fn remove_top(&mut self, ctx: &mut Context) {
+        if let Some(mut scene) = self.scenes.pop() {
+            scene.on_quit(ctx);
+        }
+    }
+    /// Does what a scene asked for
+    fn apply(&mut self, ctx: &mut Context, transition: Transition) {
+        match transition {
+            Transition::None => (),
+            Transition::Push(scene) => self.scenes.push(scene),
+            Transition::Pop => {
+                self.remove_top(ctx);
+            }
+            Transition::Replace(scene) => {
+                self.remove_top(ctx);
+                self.scenes.push(scene);
+            }
+            Transition::Reset(scene) => {
+                // Let the game under any menus save before it's thrown away
+                while !self.scenes.is_empty() {
+                    self.remove_top(ctx);
+                }
+                self.scenes.push(scene);
+            }
+            Transition::Quit => ctx.quit().unwrap(),
+        }
+        if self.scenes.is_empty() {
+            ctx.quit().unwrap();
+        }
+    }
+}
+
+impl EventHandler for SceneStack {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        // Changing the window mode doesn't give a resize event, so look for it here too
+        self.resize(ctx);
+        let changed = self.watcher.as_mut().map(Watcher::changed).unwrap_or_default();
+        if !changed.is_empty() {
+            self.reload(ctx, &changed);
+        }
+        let transition = match self.scenes.last_mut() {
+            Some(scene) => scene.update(ctx, &mut self.shared)?,
+            None => Transition::None,
+        };
+        self.apply(ctx, transition);
+        Ok(())
+    }
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        // Clear the screen first
+        graphics::clear(ctx);
+
+        // Start from the first scene that isn't drawn on top of another
+        let mut bottom = self.scenes.len().saturating_sub(1);
+        while bottom > 0 && self.scenes[bottom].is_overlay() {
+            bottom -= 1;
+        }
+        for scene in &mut self.scenes[bottom..] {
+            scene.draw(ctx, &self.shared)?;
+        }
+
+        // Save the frame before the status is drawn over it
+        if self.screenshot {
+            self.screenshot = false;
+            match screenshot::screenshot(ctx) {
+                Ok(path) => self.set_status(format!("Saved {}", path.display()), false),
+                Err(e) => self.set_status(format!("Couldn't save screenshot: {}", e), true),
+            }
+        }
+        self.draw_status(ctx)?;
+
+        // Flip the buffers to see what we just drew
+        graphics::present(ctx);
+
+        // Give the computer some time to do other things
+        timer::yield_now();
+        Ok(())
+    }
+    fn key_down_event(&mut self, ctx: &mut Context, keycode: Keycode, _: Mod, repeat: bool) {
+        // If this is a repeat event, we don't care
+        if repeat {
+            return
+        }
+        let transition = match self.scenes.last_mut() {
+            Some(scene) => {
+                // Keys the UI uses don't go to the scene
+                if scene.ui().map(|ui| ui.key_down(keycode)).unwrap_or(false) {
+                    Transition::None
+                } else {
+                    scene.key_down(ctx, &mut self.shared, keycode)
+                }
+            }
+            None => Transition::None,
+        };
+        self.apply(ctx, transition);
+    }
+    fn key_up_event(&mut self, ctx: &mut Context, keycode: Keycode, _: Mod, repeat: bool) {
+        if repeat {
+            return
+        }
+        // Take a screenshot on F12 in any scene
+        if keycode == Keycode::F12 {
+            return self.take_screenshot()
+        }
+        let transition = match self.scenes.last_mut() {
+            Some(scene) => scene.key_up(ctx, &mut self.shared, keycode),
+            None => Transition::None,
+        };
+        self.apply(ctx, transition);
+    }
+    fn mouse_button_down_event(&mut self, ctx: &mut Context, btn: MouseButton, x: i32, y: i32) {
+        let p = Point2::new(x as f32, y as f32);
+        let transition = match self.scenes.last_mut() {
+            Some(scene) => {
+                if let Some(ui) = scene.ui() {
+                    ui.mouse_down(btn, p);
+                }
+                scene.mouse_down(ctx, &mut self.shared, btn, p)
+            }
+            None => Transition::None,
+        };
+        self.apply(ctx, transition);
+    }
+    fn mouse_button_up_event(&mut self, ctx: &mut Context, btn: MouseButton, x: i32, y: i32) {
+        let p = Point2::new(x as f32, y as f32);
+        let transition = match self.scenes.last_mut() {
+            Some(scene) => {
+                if let Some(ui) = scene.ui() {
+                    ui.mouse_up(btn, p);
+                }
+                scene.mouse_up(ctx, &mut self.shared, btn, p)
+            }
+            None => Transition::None,
+        };
+        self.apply(ctx, transition);
+    }
+    fn mouse_motion_event(&mut self, ctx: &mut Context, _: MouseState, x: i32, y: i32, _: i32, _: i32) {
+        let p = Point2::new(x as f32, y as f32);
+        if let Some(scene) = self.scenes.last_mut() {
+            if let Some(ui) = scene.ui() {
+                ui.mouse_motion(p);
+            }
+            scene.mouse_motion(ctx, &mut self.shared, p);
+        }
+    }
+    fn mouse_wheel_event(&mut self, ctx: &mut Context, _x: i32, y: i32) {
+        if let Some(scene) = self.scenes.last_mut() {
+            scene.mouse_wheel(ctx, &mut self.shared, y);
+        }
+    }
+    fn resize_event(&mut self, ctx: &mut Context, _width: u32, _height: u32) {
+        self.resize(ctx);
+    }
+    fn quit_event(&mut self, ctx: &mut Context) -> bool {
+        for scene in &mut self.scenes {
+            scene.on_quit(ctx);
+        }
+        false
+    }
+}
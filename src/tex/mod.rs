@@ -1,93 +1,284 @@
-use ggez::{Context, GameResult};
-use ggez::graphics::{Image, Font, Text, Point2, Drawable};
-
-macro_rules! sprites {
-    ($(
-        $name:ident,
-        $tex:ident,
-        $width:expr,
-        $height:expr,
-        $radius: expr,
-    )*) => (
-        #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
-        /// An object to reference a sprite in the `Assets`
-        #[allow(missing_docs)]
-        pub enum Sprite {
-            $($name,)*
+use std::collections::HashMap;
+use std::io::Read;
+
+use ggez::{Context, GameResult, GameError};
+use ggez::graphics::{Image, Font, Text, Point2, Rect, Drawable, Color};
+
+mod watch;
+pub use self::watch::Watcher;
+
+/// Path of the file listing all the sprites
+const MANIFEST_FILE: &str = "/sprites.toml";
+/// Colour of the texture used in place of images that couldn't be loaded
+const PLACEHOLDER: Color = Color{r:1.,g:0.,b:1.,a:1.};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// An object to reference a sprite in the `Assets`
+///
+/// These are the sprites the game itself uses, so they have to be in the manifest
+#[allow(missing_docs)]
+pub enum Sprite {
+    ShipOn,
+    ShipOff,
+    ShipLit,
+    ShipSpeed2,
+    ShipSpeed3,
+    Asteroid,
+    StarsBg,
+    Fuel,
+    Bullet,
+}
+
+impl Sprite {
+    /// All the sprites
+    pub const ALL: [Sprite; 9] = [
+        Sprite::ShipOn, Sprite::ShipOff, Sprite::ShipLit, Sprite::ShipSpeed2, Sprite::ShipSpeed3,
+        Sprite::Asteroid, Sprite::StarsBg, Sprite::Fuel, Sprite::Bullet,
+    ];
+    /// The id of the sprite in the manifest
+    pub fn id(&self) -> &'static str {
+        match *self {
+            Sprite::ShipOn => "ship_on",
+            Sprite::ShipOff => "ship_off",
+            Sprite::ShipLit => "ship_lit",
+            Sprite::ShipSpeed2 => "ship_speed2",
+            Sprite::ShipSpeed3 => "ship_speed3",
+            Sprite::Asteroid => "asteroid",
+            Sprite::StarsBg => "stars_bg",
+            Sprite::Fuel => "fuel",
+            Sprite::Bullet => "bullet",
         }
+    }
+}
 
-        impl Sprite {
-            /// Width of the sprite
-            pub fn width(&self) -> f32 {
-                match *self {
-                    $(
-                        Sprite::$name => $width,
-                    )*
-                }
-            }
-            /// Height of the sprite
-            pub fn height(&self) -> f32 {
-                match *self {
-                    $(
-                        Sprite::$name => $height,
-                    )*
-                }
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+/// The shape of a sprite used for collisions
+pub enum Shape {
+    /// A circle with the radius of the sprite
+    Circle,
+    /// Nothing collides with it
+    None,
+}
+
+impl Default for Shape {
+    fn default() -> Self {
+        Shape::Circle
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+/// What an animation does after its last frame
+pub enum LoopMode {
+    /// Start over from the first frame
+    Loop,
+    /// Stay on the last frame
+    Once,
+    /// Go back through the frames and then forward again
+    PingPong,
+}
+
+impl Default for LoopMode {
+    fn default() -> Self {
+        LoopMode::Loop
+    }
+}
+
+fn default_pivot() -> [f32; 2] {
+    [0.5, 0.5]
+}
+fn default_frame_time() -> f32 {
+    0.1
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A sprite as it's listed in the manifest
+pub struct SpriteInfo {
+    /// The name used for the sprite
+    pub id: String,
+    /// Path of the image in the resources
+    pub path: String,
+    /// Width of the sprite
+    pub width: f32,
+    /// Height of the sprite
+    pub height: f32,
+    /// The point the sprite is placed and rotated around, as fractions of the width and height
+    #[serde(default = "default_pivot")]
+    pub pivot: [f32; 2],
+    /// The shape used for collisions
+    #[serde(default)]
+    pub shape: Shape,
+    /// Radius of the circle in the image that is scaled to fit the size of objects
+    #[serde(default)]
+    pub radius: f32,
+    /// Parts of the image that are frames of an animation, as `[x, y, width, height]` in pixels
+    ///
+    /// The whole image is drawn if there are none
+    #[serde(default)]
+    pub frames: Vec<[f32; 4]>,
+    /// Seconds each frame is shown for
+    #[serde(default = "default_frame_time")]
+    pub frame_time: f32,
+    /// What the animation does after the last frame
+    #[serde(default)]
+    pub loop_mode: LoopMode,
+}
+
+impl SpriteInfo {
+    /// Radius of the sprite used to scale it to the radius of objects
+    ///
+    /// Sprites nothing collides with have no circle, so half their size is used
+    pub fn draw_radius(&self) -> f32 {
+        match self.shape {
+            Shape::Circle => self.radius,
+            Shape::None => self.width.max(self.height) / 2.,
+        }
+    }
+    /// The pivot as an offset for `DrawParam`
+    pub fn offset(&self) -> Point2 {
+        Point2::new(self.pivot[0], self.pivot[1])
+    }
+    /// The frame shown after the animation has played for `time` seconds
+    pub fn frame_at(&self, time: f32) -> usize {
+        let n = self.frames.len();
+        if n <= 1 || self.frame_time <= 0. {
+            return 0
+        }
+        let i = (time / self.frame_time) as usize;
+        match self.loop_mode {
+            LoopMode::Loop => i % n,
+            LoopMode::Once => i.min(n - 1),
+            LoopMode::PingPong => {
+                // Forwards and back without showing the ends twice
+                let period = 2 * (n - 1);
+                let i = i % period;
+                if i < n { i } else { period - i }
             }
-            /// Radius to use with collision physics
-            pub fn radius(&self) -> f32 {
-                match *self {
-                    $(
-                        Sprite::$name => $radius,
-                    )*
-                }
+        }
+    }
+    /// The part of the image to draw for a frame, as fractions of the size of the image like `DrawParam::src`
+    pub fn src(&self, img: &Image, frame: usize) -> Rect {
+        match self.frames.get(frame) {
+            Some(f) => {
+                let (w, h) = (img.width() as f32, img.height() as f32);
+                Rect::new(f[0] / w, f[1] / h, f[2] / w, f[3] / h)
             }
+            None => Rect::one(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+/// The layout of the manifest file
+struct Manifest {
+    sprites: Vec<SpriteInfo>,
+}
+
+/// Reads the sprite manifest
+fn load_manifest(ctx: &mut Context) -> GameResult<Vec<SpriteInfo>> {
+    let mut file = ctx.filesystem.open(MANIFEST_FILE)?;
+    let mut s = String::new();
+    file.read_to_string(&mut s)?;
+    parse_manifest(&s)
+}
+
+/// Parses the text of a sprite manifest
+fn parse_manifest(s: &str) -> GameResult<Vec<SpriteInfo>> {
+    let manifest: Manifest = ::toml::from_str(s)?;
+    Ok(manifest.sprites)
+}
+
+/// Finds the index of each sprite by its id and the index of each `Sprite`
+///
+/// Fails if an id is used twice or a sprite the game uses is missing
+fn index_sprites(sprites: &[SpriteInfo]) -> GameResult<(HashMap<String, usize>, Vec<usize>)> {
+    let mut ids = HashMap::new();
+    for (i, info) in sprites.iter().enumerate() {
+        if ids.insert(info.id.clone(), i).is_some() {
+            return Err(GameError::ResourceLoadError(format!("Sprite `{}` is in {} more than once", info.id, MANIFEST_FILE)))
         }
-        /// All the assets
-        pub struct Assets {
-            $(
-                $tex: Image,
-            )*
-            /// The font used for all the text
-            pub font: Font,
+    }
+    let mut builtin = Vec::with_capacity(Sprite::ALL.len());
+    for s in Sprite::ALL.iter() {
+        match ids.get(s.id()) {
+            Some(&i) => builtin.push(i),
+            None => return Err(GameError::ResourceLoadError(format!("Sprite `{}` is missing from {}", s.id(), MANIFEST_FILE))),
         }
+    }
+    Ok((ids, builtin))
+}
 
-        impl Assets {
-            /// Initialises the assets with the context
-            pub fn new(ctx: &mut Context) -> GameResult<Self> {
-                $(
-                    let $tex = Image::new(ctx, concat!("/", stringify!($tex), ".png"))?;
-                )*
-
-                Ok((Assets {
-                    $(
-                        $tex,
-                    )*
-                    font: Font::new(ctx, "/FiraMono.ttf", 14)?,
-                }))
-            }
-            /// Gets the `Image` to draw from the sprite
-            pub fn get_img(&self, s: Sprite) -> &Image {
-                match s {
-                    $(
-                        Sprite::$name => &self.$tex,
-                    )*
-                }
-            }
+/// Loads the image of a sprite, or makes a placeholder of the same size if that fails
+///
+/// The reason it failed is added to `errors`
+fn load_image(ctx: &mut Context, info: &SpriteInfo, errors: &mut Vec<String>) -> GameResult<Image> {
+    match Image::new(ctx, &info.path) {
+        Ok(img) => Ok(img),
+        Err(e) => {
+            let error = format!("Couldn't load image {} of sprite `{}`: {}", info.path, info.id, e);
+            println!("{}, using a placeholder", error);
+            errors.push(error);
+            let size = info.width.max(info.height).max(1.) as u16;
+            Image::solid(ctx, size, PLACEHOLDER)
         }
-    );
+    }
+}
+
+/// All the assets
+pub struct Assets {
+    /// Every sprite in the manifest
+    sprites: Vec<SpriteInfo>,
+    images: Vec<Image>,
+    /// Index of each sprite by its id
+    ids: HashMap<String, usize>,
+    /// Index of each `Sprite`
+    builtin: Vec<usize>,
+    /// What went wrong loading the images
+    errors: Vec<String>,
+    /// The font used for all the text
+    pub font: Font,
 }
 
-/// Load all assets and specify their dimensions
-sprites! {
-    ShipOn, ship_on, 48., 48., 20.,
-    ShipOff, ship_off, 48., 48., 20.,
-    ShipLit, ship_lit, 48., 48., 20.,
-    ShipSpeed2, ship_speed2, 48., 48., 20.,
-    ShipSpeed3, ship_speed3, 48., 48., 20.,
-    Asteroid, asteroid, 48., 48., 24.,
-    StarsBg, stars_bg, 2560., 1440., 0./0.,
-    Fuel, fuel, 32., 32., 16.,
-    Bullet, bullet, 16., 16., 7.5,
+impl Assets {
+    /// Initialises the assets with the context
+    ///
+    /// Images that can't be loaded are replaced by a placeholder,
+    /// but the manifest has to have all the sprites the game uses
+    pub fn new(ctx: &mut Context) -> GameResult<Self> {
+        let sprites = load_manifest(ctx)?;
+        let (ids, builtin) = index_sprites(&sprites)?;
+        let mut images = Vec::with_capacity(sprites.len());
+        let mut errors = Vec::new();
+        for info in &sprites {
+            images.push(load_image(ctx, info, &mut errors)?);
+        }
+
+        Ok(Assets {
+            sprites,
+            images,
+            ids,
+            builtin,
+            errors,
+            font: Font::new(ctx, "/FiraMono.ttf", 14)?,
+        })
+    }
+    /// Gets the `Image` to draw from the sprite
+    pub fn get_img(&self, s: Sprite) -> &Image {
+        &self.images[self.builtin[s as usize]]
+    }
+    /// Gets the manifest entry of the sprite
+    pub fn info(&self, s: Sprite) -> &SpriteInfo {
+        &self.sprites[self.builtin[s as usize]]
+    }
+    /// What went wrong loading the images that were replaced by placeholders
+    pub fn errors(&self) -> &[String] {
+        &self.errors
+    }
+    /// Finds any sprite in the manifest by its id
+    pub fn find(&self, id: &str) -> Option<(&SpriteInfo, &Image)> {
+        self.ids.get(id).map(|&i| (&self.sprites[i], &self.images[i]))
+    }
 }
 
 impl Assets {
@@ -99,14 +290,6 @@ impl Assets {
             text
         })
     }
-    /// Make a postional text object from the right side of the screen
-    pub fn text_ra(&self, context: &mut Context, x: f32, y: f32, text: &str) -> GameResult<PosText> {
-        let text = Text::new(context, text, &self.font)?;
-        Ok(PosText{
-            pos: Point2::new(x - text.width() as f32, y),
-            text
-        })
-    }
 }
 
 #[derive(Debug, Clone)]
@@ -123,6 +306,14 @@ impl PosText {
     pub fn draw_text(&self, ctx: &mut Context) -> GameResult<()> {
         self.text.draw(ctx, self.pos, 0.)
     }
+    /// Moves the text
+    pub fn set_pos(&mut self, pos: Point2) {
+        self.pos = pos;
+    }
+    /// The area the text covers on the screen
+    pub fn rect(&self) -> Rect {
+        Rect::new(self.pos.x, self.pos.y, self.text.width() as f32, self.text.height() as f32)
+    }
     /// Update the text
     pub fn update_text(&mut self, a: &Assets, ctx: &mut Context, text: &str) -> GameResult<()> {
         if text != self.text.contents() {
@@ -131,3 +322,100 @@ impl PosText {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest() -> Vec<SpriteInfo> {
+        parse_manifest(include_str!("../../resources/sprites.toml")).unwrap()
+    }
+    fn error(result: GameResult<(HashMap<String, usize>, Vec<usize>)>) -> String {
+        match result {
+            Err(GameError::ResourceLoadError(e)) => e,
+            Err(e) => panic!("wrong error: {}", e),
+            Ok(_) => panic!("no error"),
+        }
+    }
+
+    #[test]
+    fn manifest_has_all_sprites() {
+        let sprites = manifest();
+        let (ids, builtin) = index_sprites(&sprites).unwrap();
+        assert_eq!(ids.len(), sprites.len());
+        for (s, &i) in Sprite::ALL.iter().zip(&builtin) {
+            assert_eq!(sprites[i].id, s.id());
+        }
+    }
+
+    fn animated(loop_mode: LoopMode) -> SpriteInfo {
+        SpriteInfo {
+            frames: vec![[0., 0., 8., 8.], [8., 0., 8., 8.], [16., 0., 8., 8.]],
+            frame_time: 0.5,
+            loop_mode,
+            .. manifest().remove(0)
+        }
+    }
+    /// The frames shown each half second from the start
+    fn frames(info: &SpriteInfo) -> Vec<usize> {
+        (0..8).map(|i| info.frame_at(i as f32 * 0.5 + 0.25)).collect()
+    }
+
+    #[test]
+    fn animated_sprite_in_manifest() {
+        let sprites = manifest();
+        let fuel = sprites.iter().find(|info| info.id == "fuel").unwrap();
+        assert_eq!(fuel.frames.len(), 4);
+        assert_eq!(fuel.loop_mode, LoopMode::PingPong);
+    }
+
+    #[test]
+    fn frame_at_loop() {
+        assert_eq!(frames(&animated(LoopMode::Loop)), [0, 1, 2, 0, 1, 2, 0, 1]);
+    }
+
+    #[test]
+    fn frame_at_once() {
+        assert_eq!(frames(&animated(LoopMode::Once)), [0, 1, 2, 2, 2, 2, 2, 2]);
+    }
+
+    #[test]
+    fn frame_at_ping_pong() {
+        assert_eq!(frames(&animated(LoopMode::PingPong)), [0, 1, 2, 1, 0, 1, 2, 1]);
+    }
+
+    #[test]
+    fn frame_at_without_frames() {
+        let mut info = animated(LoopMode::Loop);
+        info.frames.clear();
+        assert_eq!(frames(&info), [0; 8]);
+        info.frames.push([0., 0., 8., 8.]);
+        assert_eq!(frames(&info), [0; 8]);
+    }
+
+    #[test]
+    fn draw_radius_without_shape() {
+        let mut info = manifest().remove(0);
+        info.width = 40.;
+        info.height = 30.;
+        info.shape = Shape::None;
+        assert_eq!(info.draw_radius(), 20.);
+    }
+
+    #[test]
+    fn duplicate_id() {
+        let mut sprites = manifest();
+        let copy = sprites[0].clone();
+        sprites.push(copy);
+        let e = error(index_sprites(&sprites));
+        assert!(e.contains("`ship_on`") && e.contains("more than once"), "{}", e);
+    }
+
+    #[test]
+    fn missing_id() {
+        let mut sprites = manifest();
+        sprites.retain(|info| info.id != "bullet");
+        let e = error(index_sprites(&sprites));
+        assert!(e.contains("`bullet`") && e.contains("missing"), "{}", e);
+    }
+}
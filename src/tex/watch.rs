@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Milliseconds between looking at the files
+const CHECK_INTERVAL: u64 = 500;
+
+/// Notices when the files in a directory change
+///
+/// Used in development to reload the resources while the game is running
+pub struct Watcher {
+    dir: PathBuf,
+    /// When each file was last modified
+    times: HashMap<PathBuf, SystemTime>,
+    last_check: Instant,
+}
+
+impl Watcher {
+    /// Starts watching the files in `dir`
+    pub fn new(dir: PathBuf) -> Self {
+        let mut watcher = Watcher {
+            dir,
+            times: HashMap::new(),
+            last_check: Instant::now(),
+        };
+        watcher.scan();
+        watcher
+    }
+    /// Reads when the files were modified and returns the names of those that changed
+    fn scan(&mut self) -> Vec<String> {
+        let mut changed = Vec::new();
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(_) => return changed,
+        };
+        for entry in entries.filter_map(Result::ok) {
+            let modified = match entry.metadata().and_then(|m| m.modified()) {
+                Ok(modified) => modified,
+                Err(_) => continue,
+            };
+            if self.times.insert(entry.path(), modified) != Some(modified) {
+                changed.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+        changed
+    }
+    /// The names of the files that have changed since the last time
+    ///
+    /// The files are only looked at every half second, so this can be called every frame
+    pub fn changed(&mut self) -> Vec<String> {
+        if self.last_check.elapsed() < Duration::from_millis(CHECK_INTERVAL) {
+            return Vec::new()
+        }
+        self.last_check = Instant::now();
+        self.scan()
+    }
+}
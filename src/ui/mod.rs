@@ -0,0 +1,465 @@
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use ::*;
+use ggez::graphics::{DrawMode, Rect};
+
+/// Space between widgets
+const SPACING: f32 = 4.;
+/// Height of a line of text
+const LINE_HEIGHT: f32 = 16.;
+/// Height of progress bars and sliders
+const BAR_HEIGHT: f32 = 10.;
+/// Width and height of the box of a checkbox
+const CHECK_SIZE: f32 = 12.;
+/// Space around the text of a button
+const BUTTON_PADDING: f32 = 4.;
+
+/// Colour of text
+pub const TEXT: Color = Color{r:1.,g:1.,b:1.,a:1.};
+/// Colour of the focused widget
+pub const FOCUS: Color = Color{r:1.,g:0.85,b:0.3,a:1.};
+/// Colour of buttons and empty bars
+pub const WIDGET: Color = Color{r:0.25,g:0.25,b:0.3,a:0.8};
+/// Colour of filled bars and checked boxes
+pub const FILL: Color = Color{r:0.4,g:0.7,b:1.,a:1.};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// Where on the screen a panel is placed
+pub enum Anchor {
+    /// Top left corner
+    TopLeft,
+    /// Top right corner
+    TopRight,
+    /// Bottom left corner
+    BottomLeft,
+    /// Bottom right corner
+    BottomRight,
+    /// The middle of the screen
+    Center,
+}
+
+impl Anchor {
+    /// Whether widgets line up with the right side of the panel
+    fn right_aligned(&self) -> bool {
+        match *self {
+            Anchor::TopRight | Anchor::BottomRight => true,
+            _ => false,
+        }
+    }
+}
+
+/// Something to draw when the UI is drawn
+enum Command {
+    Text(String, Point2, Color),
+    Rect(Rect, Color),
+    Outline(Rect, Color),
+    Line(Point2, Point2, Color),
+}
+
+/// A panel being laid out
+struct Panel {
+    anchor: Anchor,
+    origin: Point2,
+    /// Width of the panel last frame, used for aligning to the right
+    width: f32,
+    cursor: f32,
+    size: Vector2,
+}
+
+/// An immediate mode UI
+///
+/// The widgets are made again every frame between `begin` and `end` and
+/// return what the user did with them. Panels are placed with the size they had
+/// the frame before, since it isn't known until all its widgets have been made.
+pub struct Ui {
+    assets: Rc<Assets>,
+    width: f32,
+    height: f32,
+    /// Texts made so far, by what they say
+    texts: HashMap<String, PosText>,
+    used_texts: HashSet<String>,
+    commands: Vec<Command>,
+    panel: Option<Panel>,
+    panel_sizes: Vec<Vector2>,
+    next_panel_sizes: Vec<Vector2>,
+
+    mouse: Point2,
+    mouse_held: bool,
+    mouse_pressed: bool,
+    mouse_released: bool,
+    mouse_moved: bool,
+
+    /// The widget with keyboard focus
+    focus: Option<usize>,
+    /// The widget being dragged
+    active: Option<usize>,
+    /// Number of focusable widgets this frame and the last
+    widgets: usize,
+    /// Where the focusable widgets of this frame are
+    widget_rects: Vec<Rect>,
+    last_widgets: usize,
+    nav: i32,
+    adjust: i32,
+    activate: bool,
+}
+
+impl Ui {
+    /// Makes a UI for a screen of the given size
+    pub fn new(assets: Rc<Assets>, width: u32, height: u32) -> Self {
+        Ui {
+            assets,
+            width: width as f32,
+            height: height as f32,
+            texts: HashMap::new(),
+            used_texts: HashSet::new(),
+            commands: Vec::new(),
+            panel: None,
+            panel_sizes: Vec::new(),
+            next_panel_sizes: Vec::new(),
+            mouse: Point2::new(0., 0.),
+            mouse_held: false,
+            mouse_pressed: false,
+            mouse_released: false,
+            mouse_moved: false,
+            focus: None,
+            active: None,
+            widgets: 0,
+            widget_rects: Vec::new(),
+            last_widgets: 0,
+            nav: 0,
+            adjust: 0,
+            activate: false,
+        }
+    }
+    /// Uses newly loaded assets, making the texts again with the new font
+    pub fn set_assets(&mut self, assets: Rc<Assets>) {
+        self.assets = assets;
+        self.texts.clear();
+    }
+    /// Changes the size of the screen
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.width = width as f32;
+        self.height = height as f32;
+    }
+
+    // Input
+
+    /// Handle the mouse moving
+    pub fn mouse_motion(&mut self, p: Point2) {
+        self.mouse = p;
+        self.mouse_moved = true;
+    }
+    /// Handle a mouse button being pressed
+    pub fn mouse_down(&mut self, btn: MouseButton, p: Point2) {
+        if btn == MouseButton::Left {
+            self.mouse = p;
+            self.mouse_held = true;
+            self.mouse_pressed = true;
+        }
+    }
+    /// Handle a mouse button being released
+    pub fn mouse_up(&mut self, btn: MouseButton, p: Point2) {
+        if btn == MouseButton::Left {
+            self.mouse = p;
+            self.mouse_held = false;
+            self.mouse_released = true;
+        }
+    }
+    /// Whether a point is on a widget that can be clicked, so the click shouldn't go to anything under it
+    pub fn is_over_widget(&self, p: Point2) -> bool {
+        self.widget_rects.iter().any(|r| r.contains(p))
+    }
+    /// Handle a key being pressed
+    ///
+    /// Returns whether the UI used it
+    pub fn key_down(&mut self, keycode: Keycode) -> bool {
+        use Keycode::*;
+        match keycode {
+            Up | W => self.nav -= 1,
+            Down | S | Tab => self.nav += 1,
+            Left | A => self.adjust -= 1,
+            Right | D => self.adjust += 1,
+            Return | Space => self.activate = true,
+            _ => return false,
+        }
+        true
+    }
+
+    // Frame
+
+    /// Starts making the widgets of a frame
+    pub fn begin(&mut self) {
+        self.commands.clear();
+        self.used_texts.clear();
+        self.last_widgets = self.widgets;
+        self.widgets = 0;
+        self.widget_rects.clear();
+        ::std::mem::swap(&mut self.panel_sizes, &mut self.next_panel_sizes);
+        self.next_panel_sizes.clear();
+
+        if self.last_widgets > 0 && self.nav != 0 {
+            let n = self.last_widgets as i32;
+            let current = self.focus.map(|f| f as i32).unwrap_or(if self.nav > 0 { -1 } else { 0 });
+            self.focus = Some((((current + self.nav) % n + n) % n) as usize);
+        }
+    }
+    /// Finishes the frame and forgets the input that has been handled
+    pub fn end(&mut self) {
+        if self.panel.is_some() {
+            self.end_panel();
+        }
+        if !self.mouse_held {
+            self.active = None;
+        }
+        let used = &self.used_texts;
+        self.texts.retain(|k, _| used.contains(k));
+
+        self.nav = 0;
+        self.adjust = 0;
+        self.activate = false;
+        self.mouse_pressed = false;
+        self.mouse_released = false;
+        self.mouse_moved = false;
+    }
+    /// Draws everything made this frame
+    pub fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        for command in &self.commands {
+            match *command {
+                Command::Text(ref s, p, color) => {
+                    if let Some(text) = self.texts.get_mut(s) {
+                        graphics::set_color(ctx, color)?;
+                        text.set_pos(p);
+                        text.draw_text(ctx)?;
+                    }
+                }
+                Command::Rect(r, color) => {
+                    graphics::set_color(ctx, color)?;
+                    graphics::rectangle(ctx, DrawMode::Fill, r)?;
+                }
+                Command::Outline(r, color) => {
+                    graphics::set_color(ctx, color)?;
+                    graphics::rectangle(ctx, DrawMode::Line(1.), r)?;
+                }
+                Command::Line(a, b, color) => {
+                    graphics::set_color(ctx, color)?;
+                    graphics::line(ctx, &[a, b], 1.)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Layout
+
+    /// Starts a panel placed at an anchor with a margin to the edges of the screen
+    ///
+    /// Widgets are put under each other in the panel
+    pub fn begin_panel(&mut self, anchor: Anchor, margin: f32) {
+        if self.panel.is_some() {
+            self.end_panel();
+        }
+        let i = self.next_panel_sizes.len();
+        let size = self.panel_sizes.get(i).cloned().unwrap_or_else(|| Vector2::new(0., 0.));
+        let (w, h) = (self.width, self.height);
+        let origin = match anchor {
+            Anchor::TopLeft => Point2::new(margin, margin),
+            Anchor::TopRight => Point2::new(w - margin - size.x, margin),
+            Anchor::BottomLeft => Point2::new(margin, h - margin - size.y),
+            Anchor::BottomRight => Point2::new(w - margin - size.x, h - margin - size.y),
+            Anchor::Center => Point2::new((w - size.x) / 2., (h - size.y) / 2.),
+        };
+        self.panel = Some(Panel {
+            anchor,
+            origin,
+            width: size.x,
+            cursor: 0.,
+            size: Vector2::new(0., 0.),
+        });
+    }
+    /// Ends the current panel
+    pub fn end_panel(&mut self) {
+        if let Some(panel) = self.panel.take() {
+            self.next_panel_sizes.push(panel.size);
+        }
+    }
+    /// Takes up space for a widget in the current panel
+    ///
+    /// Without a panel it's put in the top left corner
+    fn allocate(&mut self, w: f32, h: f32) -> Rect {
+        if self.panel.is_none() {
+            self.begin_panel(Anchor::TopLeft, 0.);
+        }
+        let panel = self.panel.as_mut().unwrap();
+        let x = if panel.anchor.right_aligned() {
+            panel.origin.x + panel.width - w
+        } else {
+            panel.origin.x
+        };
+        let rect = Rect::new(x, panel.origin.y + panel.cursor, w, h);
+        panel.cursor += h + SPACING;
+        panel.size.x = panel.size.x.max(w);
+        panel.size.y = panel.cursor - SPACING;
+        rect
+    }
+    /// Leaves some empty space
+    pub fn space(&mut self, h: f32) {
+        self.allocate(0., h - SPACING);
+    }
+
+    // Widgets
+
+    /// Makes sure there is a text for the string and returns its width
+    fn text_width(&mut self, ctx: &mut Context, s: &str) -> GameResult<f32> {
+        if s.is_empty() {
+            return Ok(0.)
+        }
+        self.used_texts.insert(s.to_owned());
+        if !self.texts.contains_key(s) {
+            let text = self.assets.text(ctx, Point2::new(0., 0.), s)?;
+            self.texts.insert(s.to_owned(), text);
+        }
+        Ok(self.texts[s].rect().w)
+    }
+    fn push_text(&mut self, s: &str, p: Point2, color: Color) {
+        if !s.is_empty() {
+            self.commands.push(Command::Text(s.to_owned(), p, color));
+        }
+    }
+    /// Gives the next focusable widget an id and works out how it's being used
+    ///
+    /// Returns the id, whether it's focused and whether it was clicked or activated
+    fn interact(&mut self, rect: Rect) -> (usize, bool, bool) {
+        let id = self.widgets;
+        self.widgets += 1;
+        self.widget_rects.push(rect);
+
+        let hovered = rect.contains(self.mouse);
+        if hovered && (self.mouse_moved || self.mouse_pressed) {
+            self.focus = Some(id);
+        }
+        if hovered && self.mouse_pressed {
+            self.active = Some(id);
+        }
+        let focused = self.focus == Some(id);
+        let clicked = hovered && self.mouse_released && self.active == Some(id);
+        (id, focused, clicked || (focused && self.activate))
+    }
+    /// A line of text
+    pub fn label(&mut self, ctx: &mut Context, s: &str) -> GameResult<()> {
+        self.label_colored(ctx, s, TEXT)
+    }
+    /// A line of text in a colour
+    pub fn label_colored(&mut self, ctx: &mut Context, s: &str, color: Color) -> GameResult<()> {
+        let w = self.text_width(ctx, s)?;
+        let rect = self.allocate(w, LINE_HEIGHT);
+        self.push_text(s, rect.point(), color);
+        Ok(())
+    }
+    /// A bar filled to `fraction` with lines across it at each of the `marks` (also fractions)
+    pub fn progress(&mut self, fraction: f32, width: f32, color: Color, marks: &[f32]) {
+        let rect = self.allocate(width, BAR_HEIGHT);
+        let filled = Rect::new(rect.x, rect.y, width * fraction.max(0.).min(1.), rect.h);
+        self.commands.push(Command::Rect(rect, WIDGET));
+        self.commands.push(Command::Rect(filled, color));
+        for &mark in marks {
+            let x = rect.x + width * mark;
+            self.commands.push(Command::Line(Point2::new(x, rect.y), Point2::new(x, rect.y + rect.h), TEXT));
+        }
+        self.commands.push(Command::Outline(rect, TEXT));
+    }
+    /// A line graph of the `values` from zero to `max` spread over the width, with lines across it at each of the `marks`
+    pub fn graph(&mut self, values: &[f32], max: f32, width: f32, height: f32, color: Color, marks: &[f32]) {
+        let rect = self.allocate(width, height);
+        let bottom = rect.y + rect.h;
+        let y = |v: f32| bottom - height * (v / max).max(0.).min(1.);
+        for &mark in marks.iter().chain(&[0.]) {
+            let line_y = y(mark);
+            self.commands.push(Command::Line(Point2::new(rect.x, line_y), Point2::new(rect.x + width, line_y), TEXT));
+        }
+        let step = width / values.len().saturating_sub(1).max(1) as f32;
+        for (i, pair) in values.windows(2).enumerate() {
+            let a = Point2::new(rect.x + step * i as f32, y(pair[0]));
+            let b = Point2::new(rect.x + step * (i + 1) as f32, y(pair[1]));
+            self.commands.push(Command::Line(a, b, color));
+        }
+    }
+    /// A button with some text
+    ///
+    /// Returns whether it was pressed
+    pub fn button(&mut self, ctx: &mut Context, s: &str) -> GameResult<bool> {
+        let w = self.text_width(ctx, s)? + 2. * BUTTON_PADDING;
+        let rect = self.allocate(w, LINE_HEIGHT + 2. * BUTTON_PADDING);
+        let (_, focused, pressed) = self.interact(rect);
+
+        self.commands.push(Command::Rect(rect, WIDGET));
+        if focused {
+            self.commands.push(Command::Outline(rect, FOCUS));
+        }
+        let color = if focused { FOCUS } else { TEXT };
+        self.push_text(s, Point2::new(rect.x + BUTTON_PADDING, rect.y + BUTTON_PADDING), color);
+        Ok(pressed)
+    }
+    /// A box that can be ticked with some text next to it
+    ///
+    /// Returns whether it was changed
+    pub fn checkbox(&mut self, ctx: &mut Context, s: &str, value: &mut bool) -> GameResult<bool> {
+        let w = CHECK_SIZE + SPACING + self.text_width(ctx, s)?;
+        let rect = self.allocate(w, LINE_HEIGHT);
+        let (_, focused, pressed) = self.interact(rect);
+        if pressed {
+            value.toggle();
+        }
+
+        let color = if focused { FOCUS } else { TEXT };
+        let check = Rect::new(rect.x, rect.y + (LINE_HEIGHT - CHECK_SIZE) / 2., CHECK_SIZE, CHECK_SIZE);
+        self.commands.push(Command::Rect(check, if *value { FILL } else { WIDGET }));
+        self.commands.push(Command::Outline(check, color));
+        self.push_text(s, Point2::new(rect.x + CHECK_SIZE + SPACING, rect.y), color);
+        Ok(pressed)
+    }
+    /// A bar that can be dragged or moved with the arrow keys to pick a value between `min` and `max`
+    ///
+    /// Returns whether it was changed
+    pub fn slider(&mut self, ctx: &mut Context, s: &str, value: &mut f32, min: f32, max: f32, width: f32) -> GameResult<bool> {
+        self.label(ctx, s)?;
+        let rect = self.allocate(width, BAR_HEIGHT);
+        let (id, focused, _) = self.interact(rect);
+
+        let old = *value;
+        if self.active == Some(id) && self.mouse_held {
+            let t = (self.mouse.x - rect.x) / rect.w;
+            *value = min + (max - min) * t.max(0.).min(1.);
+        } else if focused && self.adjust != 0 {
+            *value = (*value + self.adjust as f32 * (max - min) / 20.).max(min).min(max);
+        }
+
+        let t = (*value - min) / (max - min);
+        let color = if focused { FOCUS } else { TEXT };
+        self.commands.push(Command::Rect(rect, WIDGET));
+        self.commands.push(Command::Rect(Rect::new(rect.x, rect.y, rect.w * t, rect.h), FILL));
+        self.commands.push(Command::Outline(rect, color));
+        Ok(*value != old)
+    }
+    /// A list of items where one can be selected
+    ///
+    /// Returns the item that was clicked or activated, if any
+    pub fn list<S: AsRef<str>>(&mut self, ctx: &mut Context, items: &[S], selected: &mut usize) -> GameResult<Option<usize>> {
+        let mut chosen = None;
+        for (i, item) in items.iter().enumerate() {
+            let s = item.as_ref();
+            let w = self.text_width(ctx, s)?;
+            let rect = self.allocate(w, LINE_HEIGHT);
+            let (_, focused, pressed) = self.interact(rect);
+            if focused {
+                *selected = i;
+            }
+            if pressed {
+                chosen = Some(i);
+            }
+            let color = if i == *selected { FOCUS } else { TEXT };
+            self.push_text(s, rect.point(), color);
+        }
+        Ok(chosen)
+    }
+}